@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use thol_sprites_mini_parser::{
+    object_ref::parse_object_ref,
+    parser::{
+        parse_object,
+        types::{Number, Object, Position, Sprite},
+    },
+    writer::to_object_text,
+};
+
+/// Object file contents with a handful of sprites each, kept in memory so
+/// only parsing is timed.
+fn generate_corpus() -> Vec<String> {
+    (1..=2000u64)
+        .map(|id| {
+            let sprites = (0..id % 12 + 1)
+                .map(|i| Sprite {
+                    id: id * 16 + i,
+                    position: Position {
+                        x: Number(i as f64 * 3.5),
+                        y: Number(-(i as f64)),
+                    },
+                    parent: -1,
+                    ..Sprite::default()
+                })
+                .collect();
+            to_object_text(&Object {
+                id,
+                description: format!("Generated Object {} #comment", id),
+                sprites,
+                sprites_drawn_behind: Some(vec![0]),
+                head_index: vec![-1],
+                body_index: vec![0],
+                back_foot_index: vec![-1],
+                front_foot_index: vec![-1],
+                num_uses: 1,
+                ..Object::default()
+            })
+        })
+        .collect()
+}
+
+fn bench_object_ref(c: &mut Criterion) {
+    let corpus = generate_corpus();
+
+    let mut group = c.benchmark_group("scan descriptions");
+    group.bench_function("parse_object", |b| {
+        b.iter(|| {
+            corpus
+                .iter()
+                .filter_map(|text| parse_object(&mut text.as_str()).ok())
+                .filter(|o| o.description.contains("77"))
+                .count()
+        })
+    });
+    group.bench_function("parse_object_ref", |b| {
+        b.iter(|| {
+            corpus
+                .iter()
+                .filter_map(|text| parse_object_ref(&mut text.as_str()).ok())
+                .filter(|o| o.description.contains("77"))
+                .count()
+        })
+    });
+    group.bench_function("parse_object_ref then to_object", |b| {
+        b.iter(|| {
+            corpus
+                .iter()
+                .filter_map(|text| parse_object_ref(&mut text.as_str()).ok())
+                .map(|o| o.to_object())
+                .collect::<Vec<Object>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_object_ref);
+criterion_main!(benches);
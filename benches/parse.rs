@@ -0,0 +1,75 @@
+use std::{env, fs, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use thol_sprites_mini_parser::{
+    parser::{
+        parse,
+        types::{ColorRGB, Number, Object, Position, Sprite},
+    },
+    writer::to_object_text,
+};
+
+/// About as many objects as the game data has.
+const CORPUS_SIZE: u64 = 9600;
+
+/// Writes object files with a handful of sprites each into a temporary
+/// directory.
+fn generate_corpus() -> PathBuf {
+    let dir = env::temp_dir().join("tsmp_parse_bench_corpus");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for id in 1..=CORPUS_SIZE {
+        let sprites = (0..id % 12 + 1)
+            .map(|i| Sprite {
+                id: id * 16 + i,
+                position: Position {
+                    x: Number(i as f64 * 3.5),
+                    y: Number(-(i as f64)),
+                },
+                color: ColorRGB {
+                    r: Number(1.0),
+                    g: Number(0.5),
+                    b: Number(0.25),
+                },
+                parent: -1,
+                ..Sprite::default()
+            })
+            .collect();
+        let object = Object {
+            id,
+            description: format!("Generated Object {}", id),
+            sprites,
+            head_index: vec![-1],
+            body_index: vec![-1],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            num_uses: 1,
+            ..Object::default()
+        };
+        fs::write(dir.join(format!("{}.txt", id)), to_object_text(&object))
+            .unwrap();
+    }
+    dir
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let dir = generate_corpus();
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.bench_function("single thread", |b| {
+        b.iter(|| single_thread.install(|| parse(&dir).unwrap()))
+    });
+    group.bench_function("all cores", |b| b.iter(|| parse(&dir).unwrap()));
+    group.finish();
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
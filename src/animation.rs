@@ -0,0 +1,166 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::bail;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+
+use crate::{
+    parser::types::Object, render::render_object_sprites,
+    sprites::SpriteImages,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "png",
+        }
+    }
+}
+
+/// Which sprites are shown with `uses_remaining` uses left.
+///
+/// Going from full to the last use, the sprites in `use_vanish_index` are
+/// hidden and the ones in `use_appear_index` are shown, in proportion to
+/// the uses spent. Like `setupSpriteUseVis` in the game's `objectBank.cpp`,
+/// both go by sprite index: vanishing sprites are hidden from the last one
+/// back, so the first stays the longest, and appearing sprites are shown
+/// from the first one on.
+pub fn use_state_visibility(
+    object: &Object,
+    uses_remaining: u64,
+) -> Vec<bool> {
+    let num_uses = object.num_uses.max(1);
+    let used = num_uses - uses_remaining.clamp(1, num_uses);
+    let portion = |len: usize| {
+        (len as f64 * used as f64 / num_uses as f64).round() as usize
+    };
+    let indices = |list: &[i64]| -> Vec<usize> {
+        let mut indices: Vec<usize> = list
+            .iter()
+            .filter_map(|i| usize::try_from(*i).ok())
+            .collect();
+        indices.sort();
+        indices.dedup();
+        indices
+    };
+    let vanish = indices(&object.use_vanish_index);
+    let appear = indices(&object.use_appear_index);
+    let mut visible = vec![true; object.sprites.len()];
+
+    for (n, i) in appear.iter().enumerate() {
+        if let Some(v) = visible.get_mut(*i) {
+            *v = n < portion(appear.len());
+        }
+    }
+    for i in vanish.iter().rev().take(portion(vanish.len())) {
+        if let Some(v) = visible.get_mut(*i) {
+            *v = false;
+        }
+    }
+    visible
+}
+
+/// Renders one frame per use state, from full down to the last use.
+pub fn render_use_states(
+    object: &Object,
+    images: &mut SpriteImages,
+) -> anyhow::Result<Vec<RgbaImage>> {
+    let mut frames = vec![];
+    for uses_remaining in (1..=object.num_uses.max(1)).rev() {
+        let visible = use_state_visibility(object, uses_remaining);
+        let frame = render_object_sprites(object, images, |i| visible[i])?;
+
+        match frame {
+            Some(frame) => frames.push(frame),
+            None => bail!("object {} has no sprite images", object.id),
+        }
+    }
+    Ok(frames)
+}
+
+/// Writes the frames as a looping animation.
+pub fn write_animation(
+    frames: &[RgbaImage],
+    path: &Path,
+    format: AnimationFormat,
+    frame_delay_ms: u32,
+) -> anyhow::Result<()> {
+    let Some(first) = frames.first() else {
+        bail!("no frames to write to {}", path.display());
+    };
+    let file = BufWriter::new(File::create(path)?);
+
+    match format {
+        AnimationFormat::Gif => {
+            let mut encoder = GifEncoder::new(file);
+            encoder.set_repeat(Repeat::Infinite)?;
+            encoder.encode_frames(frames.iter().map(|f| {
+                Frame::from_parts(
+                    f.clone(),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(frame_delay_ms, 1),
+                )
+            }))?;
+        }
+        AnimationFormat::Apng => {
+            let mut encoder =
+                png::Encoder::new(file, first.width(), first.height());
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(frames.len() as u32, 0)?;
+            encoder.set_frame_delay(frame_delay_ms.try_into()?, 1000)?;
+
+            let mut writer = encoder.write_header()?;
+            for frame in frames {
+                writer.write_image_data(frame.as_raw())?;
+            }
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod use_state_visibility_tests {
+    use crate::{
+        animation::use_state_visibility,
+        parser::types::{Object, Sprite},
+    };
+
+    #[test]
+    fn test() {
+        let object = Object {
+            sprites: (0..5).map(|_| Sprite::default()).collect(),
+            num_uses: 3,
+            // Listed out of order, the game goes by sprite index anyway.
+            use_vanish_index: vec![1, 0, 2],
+            use_appear_index: vec![4],
+            ..Object::default()
+        };
+
+        assert_eq!(
+            use_state_visibility(&object, 3),
+            vec![true, true, true, true, false]
+        );
+        // The last vanishing sprite goes first.
+        assert_eq!(
+            use_state_visibility(&object, 2),
+            vec![true, true, false, true, false]
+        );
+        assert_eq!(
+            use_state_visibility(&object, 1),
+            vec![true, false, false, true, true]
+        );
+    }
+}
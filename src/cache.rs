@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::Context;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{
+    ordered_object_files, parse_object, sort_objects, types::Object,
+    ObjectOrder,
+};
+
+/// Bump whenever `Object` or the way object files are parsed changes, so
+/// caches written by older versions are thrown away.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Caches are also thrown away when written by another release of the
+/// crate, in case `SCHEMA_VERSION` wasn't bumped.
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    modified_secs: u64,
+    modified_nanos: u32,
+    size: u64,
+    /// FNV-1a hash of the file contents.
+    hash: u64,
+    /// `None` when the file isn't a valid object.
+    object: Option<Object>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheFile {
+    schema_version: u32,
+    crate_version: String,
+    /// Keyed by object file path.
+    entries: BTreeMap<PathBuf, CacheEntry>,
+}
+
+/// How many object files `parse_cached` took from the cache and how many
+/// it had to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub reused: usize,
+    pub parsed: usize,
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Each objects directory gets its own file in the cache directory.
+fn cache_path(cache_dir: &Path, objects_dir: &Path) -> PathBuf {
+    let objects_dir = fs::canonicalize(objects_dir)
+        .unwrap_or_else(|_| objects_dir.to_path_buf());
+    let key = fnv1a(objects_dir.to_string_lossy().as_bytes());
+
+    cache_dir.join(format!("objects_{:016x}.json", key))
+}
+
+/// A missing, unreadable or outdated cache is the same as an empty one.
+fn read_cache(path: &Path) -> CacheFile {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<CacheFile>(&text).ok())
+        .filter(|cache| {
+            cache.schema_version == SCHEMA_VERSION
+                && cache.crate_version == CRATE_VERSION
+        })
+        .unwrap_or_default()
+}
+
+fn cache_entry(
+    path: &Path,
+    cached: Option<&CacheEntry>,
+) -> anyhow::Result<(CacheEntry, bool)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+    let (modified_secs, modified_nanos) =
+        (modified.as_secs(), modified.subsec_nanos());
+    let size = metadata.len();
+
+    if let Some(entry) = cached.filter(|e| {
+        e.modified_secs == modified_secs
+            && e.modified_nanos == modified_nanos
+            && e.size == size
+    }) {
+        return Ok((entry.clone(), true));
+    }
+
+    let content = fs::read_to_string(path)?;
+    let hash = fnv1a(content.as_bytes());
+    // Touched but unchanged files don't need parsing again.
+    if let Some(entry) = cached.filter(|e| e.hash == hash && e.size == size) {
+        let entry = CacheEntry {
+            modified_secs,
+            modified_nanos,
+            ..entry.clone()
+        };
+        return Ok((entry, true));
+    }
+
+    let entry = CacheEntry {
+        modified_secs,
+        modified_nanos,
+        size,
+        hash,
+        object: parse_object(&mut content.as_str()).ok(),
+    };
+    Ok((entry, false))
+}
+
+/// Like `parser::parse_ordered`, but keeps the parsed objects in
+/// `cache_dir` and only parses files whose modification time, size and
+/// contents changed since the last call. The cache file is only written
+/// when an entry changed.
+pub fn parse_cached(
+    objects_dir: &Path,
+    cache_dir: &Path,
+    order: ObjectOrder,
+) -> anyhow::Result<(Vec<Object>, CacheStats)> {
+    let path = cache_path(cache_dir, objects_dir);
+    let cache = read_cache(&path);
+
+    let entries: Vec<(PathBuf, CacheEntry, bool)> =
+        ordered_object_files(objects_dir, order)?
+            .into_par_iter()
+            .map(|file| {
+                let (entry, reused) =
+                    cache_entry(&file, cache.entries.get(&file))?;
+                Ok((file, entry, reused))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+    let reused = entries.iter().filter(|(_, _, reused)| *reused).count();
+    let stats = CacheStats {
+        reused,
+        parsed: entries.len() - reused,
+    };
+    let mut objects: Vec<Object> = entries
+        .iter()
+        .filter_map(|(_, entry, _)| entry.object.clone())
+        .collect();
+    sort_objects(&mut objects, order);
+
+    // Files that are gone drop out of the cache.
+    let cache_file = CacheFile {
+        schema_version: SCHEMA_VERSION,
+        crate_version: CRATE_VERSION.to_string(),
+        entries: entries
+            .into_iter()
+            .map(|(file, entry, _)| (file, entry))
+            .collect(),
+    };
+    if cache_file != cache {
+        fs::create_dir_all(cache_dir)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string(&cache_file)?)
+            .with_context(|| {
+                format!("can't write {}", temp_path.display())
+            })?;
+        fs::rename(&temp_path, &path)?;
+    }
+
+    Ok((objects, stats))
+}
+
+#[cfg(test)]
+mod parse_cache_tests {
+    use std::{env, fs, process};
+
+    use crate::{
+        cache::{cache_path, parse_cached, CacheStats},
+        parser::{types::Object, ObjectOrder},
+        writer::to_object_text,
+    };
+
+    #[test]
+    fn test() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_parse_cache_test_{}", process::id()));
+        let (objects_dir, cache_dir) =
+            (dir.join("objects"), dir.join("cache"));
+        fs::create_dir_all(&objects_dir).unwrap();
+        let write = |id: u64, description: &str| {
+            let object = Object {
+                id,
+                description: description.to_string(),
+                head_index: vec![-1],
+                ..Object::default()
+            };
+            fs::write(
+                objects_dir.join(format!("{}.txt", id)),
+                to_object_text(&object),
+            )
+            .unwrap();
+        };
+        write(1, "Stone");
+        write(2, "Rose");
+        let parse = || {
+            parse_cached(&objects_dir, &cache_dir, ObjectOrder::Id).unwrap()
+        };
+
+        let (objects, stats) = parse();
+        assert_eq!(
+            stats,
+            CacheStats {
+                reused: 0,
+                parsed: 2
+            }
+        );
+        let cache_file = cache_path(&cache_dir, &objects_dir);
+        let written = fs::metadata(&cache_file).unwrap().modified().unwrap();
+        let (cached, stats) = parse();
+        assert_eq!(cached, objects);
+        // Nothing changed, so the cache isn't written again.
+        assert_eq!(
+            fs::metadata(&cache_file).unwrap().modified().unwrap(),
+            written
+        );
+        assert_eq!(
+            stats,
+            CacheStats {
+                reused: 2,
+                parsed: 0
+            }
+        );
+
+        write(2, "Red Rose");
+        let (objects, stats) = parse();
+        assert_eq!(
+            stats,
+            CacheStats {
+                reused: 1,
+                parsed: 1
+            }
+        );
+        assert_eq!(objects[1].description, "Red Rose");
+
+        let current = fs::read_to_string(&cache_file).unwrap();
+        for outdated in [
+            current.replacen("\"schemaVersion\":1", "\"schemaVersion\":0", 1),
+            current.replacen(
+                &format!(
+                    "\"crateVersion\":\"{}\"",
+                    env!("CARGO_PKG_VERSION")
+                ),
+                "\"crateVersion\":\"0.0.0\"",
+                1,
+            ),
+        ] {
+            assert_ne!(outdated, current);
+            fs::write(&cache_file, outdated).unwrap();
+            assert_eq!(
+                parse().1,
+                CacheStats {
+                    reused: 0,
+                    parsed: 2
+                }
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
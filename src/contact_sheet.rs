@@ -0,0 +1,204 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{
+    imageops::{self, FilterType},
+    Rgba, RgbaImage,
+};
+
+use crate::{
+    description::Description,
+    font::{draw_text, GLYPH_ADVANCE, GLYPH_HEIGHT},
+    parser::types::Object,
+    render::render_object,
+    sprites::SpriteImages,
+};
+
+const PADDING: u32 = 2;
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+const LABEL_HEIGHT: u32 = 2 * LINE_HEIGHT + PADDING;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactSheetOptions {
+    pub columns: u32,
+    pub rows: u32,
+    /// Width and height of the square each thumbnail is fitted into.
+    pub thumbnail_size: u32,
+    pub background: Rgba<u8>,
+    pub label_color: Rgba<u8>,
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        Self {
+            columns: 8,
+            rows: 6,
+            thumbnail_size: 128,
+            background: Rgba([255, 255, 255, 255]),
+            label_color: Rgba([0, 0, 0, 255]),
+        }
+    }
+}
+
+/// Lays the objects out into pages of labelled thumbnails, rendering each
+/// page only when the iterator gets to it.
+///
+/// Objects that can't be rendered still get a cell with their label.
+pub fn render_contact_sheets<'a>(
+    objects: &'a [&'a Object],
+    images: &'a mut SpriteImages,
+    options: &'a ContactSheetOptions,
+) -> impl Iterator<Item = RgbaImage> + 'a {
+    let columns = options.columns.max(1);
+    let per_page = (columns * options.rows.max(1)) as usize;
+    let cell_width = options.thumbnail_size + 2 * PADDING;
+    let cell_height = options.thumbnail_size + PADDING + LABEL_HEIGHT;
+
+    objects.chunks(per_page).map(move |page| {
+        let rows = (page.len() as u32).div_ceil(columns);
+        let mut sheet = RgbaImage::from_pixel(
+            columns * cell_width,
+            rows * cell_height,
+            options.background,
+        );
+
+        for (i, object) in page.iter().enumerate() {
+            let x = (i as u32 % columns) * cell_width;
+            let y = (i as u32 / columns) * cell_height;
+            draw_cell(&mut sheet, x, y, object, images, options);
+        }
+        sheet
+    })
+}
+
+fn draw_cell(
+    sheet: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    object: &Object,
+    images: &mut SpriteImages,
+    options: &ContactSheetOptions,
+) {
+    let size = options.thumbnail_size;
+
+    if let Ok(Some(rendered)) = render_object(object, images) {
+        let scale = (size as f64 / rendered.width() as f64)
+            .min(size as f64 / rendered.height() as f64)
+            .min(1.0);
+        let width = ((rendered.width() as f64 * scale).round() as u32).max(1);
+        let height =
+            ((rendered.height() as f64 * scale).round() as u32).max(1);
+        let thumbnail = if scale < 1.0 {
+            imageops::resize(&rendered, width, height, FilterType::Triangle)
+        } else {
+            rendered
+        };
+
+        imageops::overlay(
+            sheet,
+            &thumbnail,
+            (x + PADDING + (size - width) / 2).into(),
+            (y + PADDING + (size - height) / 2).into(),
+        );
+    }
+
+    let max_chars = (size / GLYPH_ADVANCE) as usize;
+    let label_y = y + PADDING + size + PADDING;
+    let name: String = Description::parse(&object.description)
+        .name
+        .chars()
+        .take(max_chars)
+        .collect();
+
+    draw_text(
+        sheet,
+        x + PADDING,
+        label_y,
+        &object.id.to_string(),
+        options.label_color,
+        1,
+    );
+    draw_text(
+        sheet,
+        x + PADDING,
+        label_y + LINE_HEIGHT,
+        &name,
+        options.label_color,
+        1,
+    );
+}
+
+/// Renders the contact sheets and saves them as numbered PNG files one
+/// page at a time, returning their paths.
+pub fn write_contact_sheets(
+    objects: &[&Object],
+    images: &mut SpriteImages,
+    options: &ContactSheetOptions,
+    out_dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(out_dir)?;
+
+    let mut paths = vec![];
+    for (i, sheet) in
+        render_contact_sheets(objects, images, options).enumerate()
+    {
+        let path = out_dir.join(format!("contact_sheet_{:03}.png", i + 1));
+        sheet.save(&path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod contact_sheet_tests {
+    use std::env;
+
+    use image::Rgba;
+
+    use crate::{
+        contact_sheet::{render_contact_sheets, ContactSheetOptions},
+        parser::types::Object,
+        sprites::SpriteImages,
+    };
+
+    #[test]
+    fn test_layout() {
+        let objects: Vec<Object> = (1..=5)
+            .map(|id| Object {
+                id,
+                description: "Stone".to_string(),
+                ..Object::default()
+            })
+            .collect();
+        let objects: Vec<&Object> = objects.iter().collect();
+        let options = ContactSheetOptions {
+            columns: 2,
+            rows: 2,
+            thumbnail_size: 16,
+            ..ContactSheetOptions::default()
+        };
+        let mut images =
+            SpriteImages::new(&env::temp_dir().join("tsmp_no_sprites"));
+        let pages: Vec<_> =
+            render_contact_sheets(&objects, &mut images, &options).collect();
+
+        // Cells are 16 + 2 * 2 wide, and 16 + 2 tall plus two label lines.
+        let sizes: Vec<_> =
+            pages.iter().map(|p| (p.width(), p.height())).collect();
+        assert_eq!(sizes, [(40, 68), (40, 34)]);
+
+        let (black, white) =
+            (Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255]));
+        // Ids are labelled under the thumbnail, from y = 20: the top row
+        // of `1` only has its middle pixel set, and `2`'s is full.
+        assert_eq!(*pages[0].get_pixel(2, 20), white);
+        assert_eq!(*pages[0].get_pixel(3, 20), black);
+        assert_eq!(*pages[0].get_pixel(22, 20), black);
+        assert_eq!(*pages[0].get_pixel(24, 20), black);
+        // `5` starts the second page.
+        assert_eq!(*pages[1].get_pixel(2, 20), black);
+        assert_eq!(*pages[1].get_pixel(22, 20), white);
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::parser::types::Object;
+
+/// An object description split into the parts its conventions mark out,
+/// e.g. `Bowl of $ Berries +food #needs sprites` has the display name
+/// `Bowl of $ Berries`, the tag `food` and the comment `needs sprites`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Description {
+    /// The description exactly as written.
+    pub raw: String,
+    /// Text before the first `#` without tags and markers, with runs of
+    /// whitespace collapsed. `$` placeholders are kept.
+    pub name: String,
+    /// Designer comment after the first `#`, if it isn't blank.
+    pub comment: Option<String>,
+    /// Words written as `+tag`, without the `+`.
+    pub tags: Vec<String>,
+    /// Words written as `@marker`, without the `@`.
+    pub markers: Vec<String>,
+    /// Number of `$` variable-number placeholders in the name.
+    pub placeholders: usize,
+}
+
+impl Description {
+    pub fn parse(raw: &str) -> Self {
+        let (head, comment) = match raw.split_once('#') {
+            Some((head, comment)) => (head, Some(comment.trim())),
+            None => (raw, None),
+        };
+
+        let mut tags = vec![];
+        let mut markers = vec![];
+        // Tags and markers count in the comment too, as they are often put
+        // there to keep them out of the name.
+        let words = head.split_whitespace();
+        for word in words.chain(comment.unwrap_or("").split_whitespace()) {
+            if let Some(tag) =
+                word.strip_prefix('+').filter(|t| !t.is_empty())
+            {
+                tags.push(tag.to_string());
+            } else if let Some(marker) =
+                word.strip_prefix('@').filter(|m| !m.is_empty())
+            {
+                markers.push(marker.to_string());
+            }
+        }
+
+        let name: Vec<&str> = head
+            .split_whitespace()
+            .filter(|w| !is_annotation(w))
+            .collect();
+        let name = name.join(" ");
+
+        Self {
+            raw: raw.to_string(),
+            placeholders: name.matches('$').count(),
+            name,
+            comment: comment.filter(|c| !c.is_empty()).map(str::to_string),
+            tags,
+            markers,
+        }
+    }
+
+    /// The display name with every `$` replaced by `number`.
+    pub fn name_with_number(&self, number: u64) -> String {
+        self.name.replace('$', &number.to_string())
+    }
+}
+
+/// An object as `tsmp` prints it, with the parts of its description next
+/// to the raw text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribedObject {
+    #[serde(flatten)]
+    pub object: Object,
+    pub description_parts: Description,
+}
+
+impl From<Object> for DescribedObject {
+    fn from(object: Object) -> Self {
+        Self {
+            description_parts: Description::parse(&object.description),
+            object,
+        }
+    }
+}
+
+fn is_annotation(word: &str) -> bool {
+    (word.starts_with('+') || word.starts_with('@')) && word.len() > 1
+}
+
+#[cfg(test)]
+mod description_tests {
+    use crate::{
+        description::{DescribedObject, Description},
+        parser::types::Object,
+    };
+
+    #[test]
+    fn test_parse() {
+        let description = Description::parse(
+            "Bowl of  $ Berries +food @fruit#needs +cold ",
+        );
+        assert_eq!(description.name, "Bowl of $ Berries");
+        assert_eq!(description.comment.as_deref(), Some("needs +cold"));
+        assert_eq!(description.tags, ["food", "cold"]);
+        assert_eq!(description.markers, ["fruit"]);
+        assert_eq!(description.placeholders, 1);
+        assert_eq!(description.name_with_number(3), "Bowl of 3 Berries");
+        assert_eq!(
+            description.raw,
+            "Bowl of  $ Berries +food @fruit#needs +cold "
+        );
+
+        let plain = Description::parse("Rose Crown with Rose#");
+        assert_eq!(plain.name, "Rose Crown with Rose");
+        assert_eq!(plain.comment, None);
+        assert!(plain.tags.is_empty() && plain.markers.is_empty());
+        assert_eq!(Description::parse("1 + 1").name, "1 + 1");
+    }
+
+    #[test]
+    fn test_described_object() {
+        let object = Object {
+            id: 30,
+            description: "Bowl of Stew +food #full".to_string(),
+            ..Object::default()
+        };
+        let json =
+            serde_json::to_value(DescribedObject::from(object.clone()))
+                .unwrap();
+
+        assert_eq!(json["id"], 30);
+        assert_eq!(json["description"], "Bowl of Stew +food #full");
+        assert_eq!(json["descriptionParts"]["name"], "Bowl of Stew");
+        assert_eq!(json["descriptionParts"]["tags"][0], "food");
+        // Printed objects still read back as objects.
+        assert_eq!(serde_json::from_value::<Object>(json).unwrap(), object);
+    }
+}
@@ -0,0 +1,646 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::{
+        types::{AgeRange, ColorRGB, Number, Object, Position, Sprite},
+        ObjectReader, ParseError,
+    },
+    query::kind_path,
+    sprite_diff::{
+        align_sprites, diff_index_fields, IndexFieldChange, IndexMove,
+        Pairing, SpriteAlignment,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+/// What happened to one sprite between two versions of an object. Indices
+/// are into the new sprite list, except for removed sprites.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "camelCase")]
+pub enum SpriteChange {
+    Added {
+        index: usize,
+        id: u64,
+    },
+    Removed {
+        index: usize,
+        id: u64,
+    },
+    Moved {
+        index: usize,
+        id: u64,
+        old: Position,
+        new: Position,
+    },
+    Recolored {
+        index: usize,
+        id: u64,
+        old: ColorRGB,
+        new: ColorRGB,
+    },
+    Rotated {
+        index: usize,
+        id: u64,
+        old: Number,
+        new: Number,
+    },
+    Flipped {
+        index: usize,
+        id: u64,
+        old: Number,
+        new: Number,
+    },
+    /// Ages the sprite is shown at changed.
+    Aged {
+        index: usize,
+        id: u64,
+        old: AgeRange,
+        new: AgeRange,
+    },
+    /// The parent is an index into the old sprite list for `old` and into
+    /// the new one for `new`. Parents that only shifted along with the
+    /// list aren't changes.
+    Reparented {
+        index: usize,
+        id: u64,
+        old: i64,
+        new: i64,
+    },
+    /// Same place, rotation and flip, different sprite image.
+    Replaced {
+        index: usize,
+        old: u64,
+        new: u64,
+    },
+    Reordered {
+        id: u64,
+        old: usize,
+        new: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectSummary {
+    pub id: u64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDiff {
+    pub id: u64,
+    pub description: Option<Change<String>>,
+    /// Kinds as dotted paths, e.g. `clothing.hat`.
+    pub kind: Option<Change<String>>,
+    pub sprites: Vec<SpriteChange>,
+    /// Old and new indices of the sprites whose index changed.
+    pub index_moves: Vec<IndexMove>,
+    /// Index list fields that changed other than by following
+    /// `index_moves`.
+    pub index_fields: Vec<IndexFieldChange>,
+}
+
+impl ObjectDiff {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.kind.is_none()
+            && self.sprites.is_empty()
+            && self.index_moves.is_empty()
+            && self.index_fields.is_empty()
+    }
+}
+
+/// Differences between two sets of objects, such as two data releases.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseDiff {
+    pub added: Vec<ObjectSummary>,
+    pub removed: Vec<ObjectSummary>,
+    pub changed: Vec<ObjectDiff>,
+    /// Object files of the old release that don't parse.
+    pub unparsed_old: Vec<PathBuf>,
+    /// Object files of the new release that don't parse.
+    pub unparsed_new: Vec<PathBuf>,
+}
+
+/// The objects of one release, and its object files that don't parse.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Release {
+    pub objects: Vec<Object>,
+    pub unparsed: Vec<PathBuf>,
+}
+
+impl Release {
+    pub fn read(objects_dir: &Path) -> anyhow::Result<Self> {
+        let mut release = Self::default();
+        for object in ObjectReader::new(objects_dir)? {
+            match object {
+                Ok(object) => release.objects.push(object),
+                Err(ParseError::Syntax { path, .. }) => {
+                    release.unparsed.push(path)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(release)
+    }
+
+    /// Ids of the unparsed files, taken from their names.
+    fn unparsed_ids(&self) -> BTreeSet<u64> {
+        self.unparsed
+            .iter()
+            .filter_map(|path| path.file_stem()?.to_str()?.parse().ok())
+            .collect()
+    }
+}
+
+fn summary(object: &Object) -> ObjectSummary {
+    ObjectSummary {
+        id: object.id,
+        description: object.description.clone(),
+    }
+}
+
+fn change<T: PartialEq>(old: T, new: T) -> Option<Change<T>> {
+    (old != new).then_some(Change { old, new })
+}
+
+/// Objects are matched by id, and listed in id order. Objects whose file
+/// doesn't parse in the other release are listed as unparsed rather than
+/// added or removed.
+pub fn diff_releases(old: &Release, new: &Release) -> ReleaseDiff {
+    let unparsed_ids: BTreeSet<u64> = old
+        .unparsed_ids()
+        .union(&new.unparsed_ids())
+        .copied()
+        .collect();
+    let old_objects: BTreeMap<u64, &Object> =
+        old.objects.iter().map(|o| (o.id, o)).collect();
+    let new_objects: BTreeMap<u64, &Object> =
+        new.objects.iter().map(|o| (o.id, o)).collect();
+    let mut diff = ReleaseDiff {
+        unparsed_old: old.unparsed.clone(),
+        unparsed_new: new.unparsed.clone(),
+        ..ReleaseDiff::default()
+    };
+
+    for (id, old_object) in &old_objects {
+        match new_objects.get(id) {
+            None if unparsed_ids.contains(id) => (),
+            None => diff.removed.push(summary(old_object)),
+            Some(new_object) => {
+                let object_diff = diff_objects(old_object, new_object);
+                if !object_diff.is_empty() {
+                    diff.changed.push(object_diff);
+                }
+            }
+        }
+    }
+    diff.added = new_objects
+        .values()
+        .filter(|o| {
+            !old_objects.contains_key(&o.id) && !unparsed_ids.contains(&o.id)
+        })
+        .map(|o| summary(o))
+        .collect();
+
+    diff
+}
+
+pub fn diff_objects(old: &Object, new: &Object) -> ObjectDiff {
+    let alignment = align_sprites(&old.sprites, &new.sprites);
+
+    ObjectDiff {
+        id: new.id,
+        description: change(old.description.clone(), new.description.clone()),
+        kind: change(
+            kind_path(&old.kind).to_string(),
+            kind_path(&new.kind).to_string(),
+        ),
+        sprites: sprite_changes(&old.sprites, &new.sprites, &alignment),
+        index_moves: alignment.moves(),
+        index_fields: diff_index_fields(old, new, &alignment),
+    }
+}
+
+pub fn diff_sprites(old: &[Sprite], new: &[Sprite]) -> Vec<SpriteChange> {
+    sprite_changes(old, new, &align_sprites(old, new))
+}
+
+/// Sprites that only shifted along with the list aren't changes, those are
+/// in `SpriteAlignment::moves`.
+fn sprite_changes(
+    old: &[Sprite],
+    new: &[Sprite],
+    alignment: &SpriteAlignment,
+) -> Vec<SpriteChange> {
+    let mut changes = vec![];
+    let mut added = alignment.added.iter().peekable();
+
+    for pair in &alignment.pairs {
+        while let Some(&&i) = added.peek().filter(|&&&i| i < pair.new) {
+            changes.push(SpriteChange::Added {
+                index: i,
+                id: new[i].id,
+            });
+            added.next();
+        }
+
+        let (j, i) = (pair.old, pair.new);
+        let (o, n) = (&old[j], &new[i]);
+        match pair.pairing {
+            Pairing::Kept => (),
+            Pairing::Reordered => changes.push(SpriteChange::Reordered {
+                id: n.id,
+                old: j,
+                new: i,
+            }),
+            Pairing::Replaced => changes.push(SpriteChange::Replaced {
+                index: i,
+                old: o.id,
+                new: n.id,
+            }),
+        }
+        if o.position != n.position {
+            changes.push(SpriteChange::Moved {
+                index: i,
+                id: n.id,
+                old: o.position.clone(),
+                new: n.position.clone(),
+            });
+        }
+        if o.color != n.color {
+            changes.push(SpriteChange::Recolored {
+                index: i,
+                id: n.id,
+                old: o.color.clone(),
+                new: n.color.clone(),
+            });
+        }
+        if o.rot != n.rot {
+            changes.push(SpriteChange::Rotated {
+                index: i,
+                id: n.id,
+                old: o.rot.clone(),
+                new: n.rot.clone(),
+            });
+        }
+        if o.h_flip != n.h_flip {
+            changes.push(SpriteChange::Flipped {
+                index: i,
+                id: n.id,
+                old: o.h_flip.clone(),
+                new: n.h_flip.clone(),
+            });
+        }
+        if o.age_range != n.age_range {
+            changes.push(SpriteChange::Aged {
+                index: i,
+                id: n.id,
+                old: o.age_range.clone(),
+                new: n.age_range.clone(),
+            });
+        }
+        let followed_parent = match usize::try_from(o.parent) {
+            Ok(parent) => alignment.new_index(parent).map(|p| p as i64),
+            Err(_) => Some(o.parent),
+        };
+        if followed_parent != Some(n.parent) {
+            changes.push(SpriteChange::Reparented {
+                index: i,
+                id: n.id,
+                old: o.parent,
+                new: n.parent,
+            });
+        }
+    }
+    changes.extend(added.map(|&i| SpriteChange::Added {
+        index: i,
+        id: new[i].id,
+    }));
+    changes.extend(alignment.removed.iter().map(|&j| {
+        SpriteChange::Removed {
+            index: j,
+            id: old[j].id,
+        }
+    }));
+    changes
+}
+
+struct ShowPosition<'a>(&'a Position);
+
+impl fmt::Display for ShowPosition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.0.x.0, self.0.y.0)
+    }
+}
+
+struct ShowColor<'a>(&'a ColorRGB);
+
+impl fmt::Display for ShowColor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.0.r.0, self.0.g.0, self.0.b.0)
+    }
+}
+
+impl fmt::Display for SpriteChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { index, id } => {
+                write!(f, "sprite {} ({}) added", index, id)
+            }
+            Self::Removed { index, id } => {
+                write!(f, "sprite {} ({}) removed", index, id)
+            }
+            Self::Moved {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) moved {} -> {}",
+                index,
+                id,
+                ShowPosition(old),
+                ShowPosition(new)
+            ),
+            Self::Recolored {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) recolored {} -> {}",
+                index,
+                id,
+                ShowColor(old),
+                ShowColor(new)
+            ),
+            Self::Rotated {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) rotated {} -> {}",
+                index, id, old.0, new.0
+            ),
+            Self::Flipped {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) hFlip {} -> {}",
+                index, id, old.0, new.0
+            ),
+            Self::Aged {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) ageRange ({}, {}) -> ({}, {})",
+                index, id, old.min.0, old.max.0, new.min.0, new.max.0
+            ),
+            Self::Reparented {
+                index,
+                id,
+                old,
+                new,
+            } => write!(
+                f,
+                "sprite {} ({}) parent {} -> {}",
+                index, id, old, new
+            ),
+            Self::Replaced { index, old, new } => {
+                write!(f, "sprite {} replaced {} -> {}", index, old, new)
+            }
+            Self::Reordered { id, old, new } => {
+                write!(f, "sprite {} reordered {} -> {}", id, old, new)
+            }
+        }
+    }
+}
+
+/// One line per added, removed or changed object, with the changes of an
+/// object indented below it.
+impl fmt::Display for ReleaseDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for object in &self.added {
+            writeln!(f, "+ {} {}", object.id, object.description)?;
+        }
+        for object in &self.removed {
+            writeln!(f, "- {} {}", object.id, object.description)?;
+        }
+        for object in &self.changed {
+            writeln!(f, "~ {}", object.id)?;
+            if let Some(c) = &object.description {
+                writeln!(f, "    description {:?} -> {:?}", c.old, c.new)?;
+            }
+            if let Some(c) = &object.kind {
+                writeln!(f, "    kind {} -> {}", c.old, c.new)?;
+            }
+            for sprite in &object.sprites {
+                writeln!(f, "    {}", sprite)?;
+            }
+            if !object.index_moves.is_empty() {
+                let moves: Vec<String> = object
+                    .index_moves
+                    .iter()
+                    .map(|m| format!("{} -> {}", m.old, m.new))
+                    .collect();
+                writeln!(f, "    indices {}", moves.join(", "))?;
+            }
+            for c in &object.index_fields {
+                writeln!(
+                    f,
+                    "    {} {:?} -> {:?}, sprites now at {:?}",
+                    c.field, c.old, c.new, c.remapped
+                )?;
+            }
+        }
+        for path in &self.unparsed_old {
+            writeln!(f, "! old {} doesn't parse", path.display())?;
+        }
+        for path in &self.unparsed_new {
+            writeln!(f, "! new {} doesn't parse", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod release_diff_tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        diff::{diff_releases, diff_sprites, Release, SpriteChange},
+        parser::types::{AgeRange, Number, Object, Position, Sprite},
+    };
+
+    fn sprite(id: u64, x: f64) -> Sprite {
+        Sprite {
+            id,
+            position: Position {
+                x: Number(x),
+                y: Number(0.0),
+            },
+            parent: -1,
+            ..Sprite::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_sprites() {
+        let old = [sprite(1, 0.0), sprite(2, 0.0), sprite(3, 0.0)];
+        let new = [sprite(2, 0.0), sprite(1, 5.0), sprite(4, 0.0)];
+
+        assert_eq!(
+            diff_sprites(&old, &new),
+            [
+                SpriteChange::Reordered {
+                    id: 1,
+                    old: 0,
+                    new: 1
+                },
+                SpriteChange::Moved {
+                    index: 1,
+                    id: 1,
+                    old: old[0].position.clone(),
+                    new: new[1].position.clone()
+                },
+                SpriteChange::Replaced {
+                    index: 2,
+                    old: 3,
+                    new: 4
+                },
+            ]
+        );
+        assert_eq!(
+            diff_sprites(&old, &old[..2]),
+            [SpriteChange::Removed { index: 2, id: 3 }]
+        );
+    }
+
+    #[test]
+    fn test_diff_releases() {
+        let object = |id, description: &str| Object {
+            id,
+            description: description.to_string(),
+            ..Object::default()
+        };
+        let old = Release {
+            objects: vec![object(1, "Rose"), object(2, "Stone")],
+            unparsed: vec![],
+        };
+        let new = Release {
+            objects: vec![object(3, "Basket"), object(1, "Red Rose")],
+            unparsed: vec![],
+        };
+
+        let diff = diff_releases(&old, &new);
+        assert_eq!(
+            diff.to_string(),
+            "+ 3 Basket\n- 2 Stone\n~ 1\n    description \"Rose\" -> \"Red \
+             Rose\"\n"
+        );
+
+        // Broken files aren't mistaken for removed or added objects.
+        let new = Release {
+            objects: vec![object(1, "Rose"), object(4, "Flint")],
+            unparsed: vec![
+                PathBuf::from("new/2.txt"),
+                PathBuf::from("new/4.txt"),
+            ],
+        };
+        let old = Release {
+            unparsed: vec![PathBuf::from("old/4.txt")],
+            ..old
+        };
+        assert_eq!(
+            diff_releases(&old, &new).to_string(),
+            "! old old/4.txt doesn't parse\n! new new/2.txt doesn't \
+             parse\n! new new/4.txt doesn't parse\n"
+        );
+    }
+
+    #[test]
+    fn test_sprite_fields() {
+        let old = [
+            sprite(1, 0.0),
+            Sprite {
+                parent: 0,
+                ..sprite(2, 0.0)
+            },
+        ];
+        let mut new = [
+            sprite(9, 0.0),
+            old[0].clone(),
+            Sprite {
+                parent: 1,
+                ..old[1].clone()
+            },
+        ];
+        // The parent followed its sprite to index 1.
+        assert_eq!(
+            diff_sprites(&old, &new),
+            [SpriteChange::Added { index: 0, id: 9 }]
+        );
+
+        new[2].parent = -1;
+        new[1].rot = Number(0.25);
+        new[1].h_flip = Number(1.0);
+        new[1].age_range = AgeRange {
+            min: Number(0.0),
+            max: Number(3.0),
+        };
+        assert_eq!(
+            diff_sprites(&old, &new),
+            [
+                SpriteChange::Added { index: 0, id: 9 },
+                SpriteChange::Rotated {
+                    index: 1,
+                    id: 1,
+                    old: Number(0.0),
+                    new: Number(0.25)
+                },
+                SpriteChange::Flipped {
+                    index: 1,
+                    id: 1,
+                    old: Number(0.0),
+                    new: Number(1.0)
+                },
+                SpriteChange::Aged {
+                    index: 1,
+                    id: 1,
+                    old: AgeRange::default(),
+                    new: new[1].age_range.clone()
+                },
+                SpriteChange::Reparented {
+                    index: 2,
+                    id: 2,
+                    old: 0,
+                    new: -1
+                },
+            ]
+        );
+    }
+}
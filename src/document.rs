@@ -0,0 +1,402 @@
+use anyhow::anyhow;
+use winnow::Parser;
+
+use crate::{
+    parser::{
+        parse_object,
+        types::{Number, Object},
+    },
+    writer::{float, index_list},
+};
+
+/// Typed value of a key `parse_object` knows about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    /// Comma separated floats, written like `%f`.
+    Numbers(Vec<Number>),
+    /// Comma separated sprite indices, `-1` when empty.
+    Indices(Vec<i64>),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Int,
+    Numbers,
+    Indices,
+    Text,
+}
+
+fn value_kind(key: &str) -> Option<ValueKind> {
+    match key {
+        "id" | "person" | "male" | "numSprites" | "spriteID" | "hFlip"
+        | "parent" | "invisHolding" | "invisWorn" | "behindSlots"
+        | "invisCont" | "ignoredCont" => Some(ValueKind::Int),
+        "clothingOffset" | "pos" | "rot" | "color" | "ageRange" => {
+            Some(ValueKind::Numbers)
+        }
+        "spritesDrawnBehind"
+        | "spritesAdditiveBlend"
+        | "headIndex"
+        | "bodyIndex"
+        | "backFootIndex"
+        | "frontFootIndex"
+        | "useVanishIndex"
+        | "useAppearIndex" => Some(ValueKind::Indices),
+        "clothing" | "numUses" => Some(ValueKind::Text),
+        _ => None,
+    }
+}
+
+fn parse_value(kind: ValueKind, raw: &str) -> Option<Value> {
+    match kind {
+        ValueKind::Int => raw.parse().ok().map(Value::Int),
+        ValueKind::Numbers => raw
+            .split(',')
+            .map(|n| n.parse().ok().map(Number))
+            .collect::<Option<_>>()
+            .map(Value::Numbers),
+        ValueKind::Indices => raw
+            .split(',')
+            .map(|n| n.parse().ok())
+            .collect::<Option<_>>()
+            .map(Value::Indices),
+        ValueKind::Text => Some(Value::Text(raw.to_string())),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Int(i) => i.to_string(),
+        Value::Numbers(numbers) => {
+            let items: Vec<String> = numbers.iter().map(float).collect();
+            items.join(",")
+        }
+        Value::Indices(indices) => index_list(indices),
+        Value::Text(text) => text.clone(),
+    }
+}
+
+/// One `key=value` pair, along with the `,` or `#` separating it from the
+/// previous pair on the same line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub separator: String,
+    pub key: String,
+    raw: String,
+    value: Option<Value>,
+}
+
+impl Field {
+    fn new(separator: &str, key: &str, raw: &str) -> Self {
+        Self {
+            separator: separator.to_string(),
+            key: key.to_string(),
+            raw: raw.to_string(),
+            value: value_kind(key).and_then(|kind| parse_value(kind, raw)),
+        }
+    }
+
+    /// The value exactly as it appears in the file.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The typed value, for known keys whose value is well formed.
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    pub fn set_value(&mut self, value: Value) {
+        self.raw = format_value(&value);
+        self.value = Some(value);
+    }
+
+    pub fn set_raw(&mut self, raw: &str) {
+        *self = Self::new(&self.separator, &self.key, raw);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineContent {
+    Fields(Vec<Field>),
+    /// The description line, and anything else that isn't `key=value`.
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub content: LineContent,
+    /// `"\n"`, `"\r\n"`, or empty for a last line without one.
+    pub ending: String,
+}
+
+/// Every line of an object file in order, so it can be edited and written
+/// back without losing the keys `parse_object` skips.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectDocument {
+    pub lines: Vec<Line>,
+}
+
+/// Whether `s` starts with a `key=`.
+fn starts_with_key(s: &str) -> bool {
+    let key_len = s
+        .char_indices()
+        .take_while(|(i, c)| {
+            c.is_ascii_alphabetic()
+                || (*i > 0 && (c.is_ascii_alphanumeric() || *c == '_'))
+        })
+        .count();
+
+    key_len > 0 && s[key_len..].starts_with('=')
+}
+
+fn parse_fields(line: &str) -> Option<Vec<Field>> {
+    if !starts_with_key(line) {
+        return None;
+    }
+
+    // A `,` or `#` only starts a new pair when a key follows it, since
+    // values like `sounds` contain commas too.
+    let mut starts = vec![0];
+    for (i, c) in line.char_indices() {
+        if (c == ',' || c == '#') && starts_with_key(&line[i + 1..]) {
+            starts.push(i);
+        }
+    }
+    starts.push(line.len());
+
+    let fields = starts
+        .windows(2)
+        .map(|w| {
+            let segment = &line[w[0]..w[1]];
+            let separator_len = usize::from(w[0] > 0);
+            let (separator, pair) = segment.split_at(separator_len);
+            let (key, raw) = pair.split_once('=').unwrap_or((pair, ""));
+
+            Field::new(separator, key, raw)
+        })
+        .collect();
+    Some(fields)
+}
+
+impl ObjectDocument {
+    pub fn parse(text: &str) -> Self {
+        let lines = text
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                let (body, ending) = match line.strip_suffix("\r\n") {
+                    Some(body) => (body, "\r\n"),
+                    None => match line.strip_suffix('\n') {
+                        Some(body) => (body, "\n"),
+                        None => (line, ""),
+                    },
+                };
+                // The second line is the description, whatever it holds.
+                let content = match parse_fields(body) {
+                    Some(fields) if i != 1 => LineContent::Fields(fields),
+                    _ => LineContent::Text(body.to_string()),
+                };
+
+                Line {
+                    content,
+                    ending: ending.to_string(),
+                }
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            match &line.content {
+                LineContent::Fields(fields) => {
+                    for field in fields {
+                        text.push_str(&field.separator);
+                        text.push_str(&field.key);
+                        text.push('=');
+                        text.push_str(&field.raw);
+                    }
+                }
+                LineContent::Text(body) => text.push_str(body),
+            }
+            text.push_str(&line.ending);
+        }
+        text
+    }
+
+    /// Reads the document the same way object files are parsed.
+    pub fn to_object(&self) -> anyhow::Result<Object> {
+        parse_object
+            .parse(self.to_text().as_str())
+            .map_err(|e| anyhow!("invalid object document: {}", e))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.lines.iter().flat_map(|line| match &line.content {
+            LineContent::Fields(fields) => fields.as_slice(),
+            LineContent::Text(_) => &[],
+        })
+    }
+
+    pub fn fields_mut(&mut self) -> impl Iterator<Item = &mut Field> {
+        self.lines
+            .iter_mut()
+            .flat_map(|line| match &mut line.content {
+                LineContent::Fields(fields) => fields.as_mut_slice(),
+                LineContent::Text(_) => &mut [],
+            })
+    }
+
+    /// First field with the key.
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        self.fields().find(|f| f.key == key)
+    }
+
+    pub fn field_mut(&mut self, key: &str) -> Option<&mut Field> {
+        self.fields_mut().find(|f| f.key == key)
+    }
+
+    /// The description line.
+    pub fn description(&self) -> Option<&str> {
+        match &self.lines.get(1)?.content {
+            LineContent::Text(text) => Some(text),
+            LineContent::Fields(_) => None,
+        }
+    }
+
+    pub fn set_description(&mut self, description: &str) {
+        if let Some(line) = self.lines.get_mut(1) {
+            line.content = LineContent::Text(description.to_string());
+        }
+    }
+
+    /// Fields of the sprite block at `index`, from its `spriteID` up to the
+    /// next sprite or the end of the sprite list.
+    pub fn sprite_fields_mut(
+        &mut self,
+        index: usize,
+    ) -> impl Iterator<Item = &mut Field> {
+        let mut sprite = None;
+        self.fields_mut().filter(move |f| {
+            match f.key.as_str() {
+                "spriteID" => sprite = Some(sprite.map_or(0, |s| s + 1)),
+                "spritesDrawnBehind"
+                | "spritesAdditiveBlend"
+                | "headIndex" => sprite = None,
+                _ => (),
+            }
+            sprite == Some(index)
+        })
+    }
+
+    pub fn sprite_field_mut(
+        &mut self,
+        index: usize,
+        key: &str,
+    ) -> Option<&mut Field> {
+        self.sprite_fields_mut(index).find(|f| f.key == key)
+    }
+}
+
+#[cfg(test)]
+mod object_document_tests {
+    use crate::{
+        document::{ObjectDocument, Value},
+        parser::types::Number,
+    };
+
+    const SOURCE: &str = "id=7767\r
+Rose Crown with Rose\r
+containable=0\r
+mapChance=0.000000#biomes_0\r
+person=0,noSpawn=0\r
+male=0\r
+sounds=34:0.250000,-1:0.0,-1:0.0,-1:0.0\r
+clothing=n\r
+clothingOffset=0.000000,0.000000\r
+numSlots=0#timeStretch=1.000000\r
+numSprites=2\r
+spriteID=111068\r
+pos=-1.000000,-29.000000\r
+rot=0.000000\r
+hFlip=0\r
+color=1.000000,1.000000,1.000000\r
+ageRange=-1.000000,-1.000000\r
+parent=-1\r
+invisHolding=0,invisWorn=0,behindSlots=0\r
+invisCont=0\r
+spriteID=1304\r
+pos=4.000000,-34.000000\r
+rot=-0.025000\r
+hFlip=0\r
+color=1.000000,1.000000,1.000000\r
+ageRange=-1.000000,-1.000000\r
+parent=-1\r
+invisHolding=0,invisWorn=0,behindSlots=0\r
+headIndex=-1\r
+bodyIndex=-1\r
+backFootIndex=-1\r
+frontFootIndex=-1\r
+pixHeight=0";
+
+    #[test]
+    fn test_lossless() {
+        let document = ObjectDocument::parse(SOURCE);
+
+        assert_eq!(document.to_text(), SOURCE);
+        assert_eq!(document.description(), Some("Rose Crown with Rose"));
+        assert_eq!(
+            document.field("timeStretch").map(|f| f.raw()),
+            Some("1.000000")
+        );
+        assert_eq!(
+            document.field("sounds").map(|f| f.raw()),
+            Some("34:0.250000,-1:0.0,-1:0.0,-1:0.0")
+        );
+        assert_eq!(document.field("sounds").and_then(|f| f.value()), None);
+        assert_eq!(
+            document.field("noSpawn").map(|f| f.separator.as_str()),
+            Some(",")
+        );
+        assert_eq!(document.to_object().unwrap().sprites.len(), 2);
+    }
+
+    #[test]
+    fn test_edit_sprite() {
+        let mut document = ObjectDocument::parse(SOURCE);
+        let color = document.sprite_field_mut(1, "color").unwrap();
+        assert_eq!(
+            color.value(),
+            Some(&Value::Numbers(vec![
+                Number(1.0),
+                Number(1.0),
+                Number(1.0)
+            ]))
+        );
+        color.set_value(Value::Numbers(vec![
+            Number(0.5),
+            Number(0.25),
+            Number(1.0),
+        ]));
+        assert!(document.sprite_field_mut(1, "invisCont").is_none());
+        assert!(document.sprite_field_mut(2, "color").is_none());
+
+        let (head, tail) =
+            SOURCE.split_at(SOURCE.find("spriteID=1304").unwrap());
+        let expected = tail.replacen(
+            "color=1.000000,1.000000,1.000000",
+            "color=0.500000,0.250000,1.000000",
+            1,
+        );
+        assert_eq!(document.to_text(), format!("{}{}", head, expected));
+        assert_eq!(
+            document.to_object().unwrap().sprites[1].color.g,
+            Number(0.25)
+        );
+    }
+}
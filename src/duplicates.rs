@@ -0,0 +1,383 @@
+use std::{collections::BTreeMap, path::Path};
+
+use image::{
+    imageops::{self, FilterType},
+    GrayImage, Luma, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::types::{Number, Object},
+    sprite_index::SpriteIndex,
+    sprites::{load_sprite_image, sprite_files},
+    stats::sprite_stats,
+};
+
+/// Largest per channel difference between the average colors of two
+/// sprites that still look the same.
+const COLOR_TOLERANCE: u8 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteHash {
+    /// Difference hash of the sprite's brightness.
+    pub shape: u64,
+    /// Average color, which the brightness hash can't tell apart.
+    pub color: [u8; 3],
+}
+
+impl SpriteHash {
+    pub fn is_similar(&self, other: &SpriteHash, max_distance: u32) -> bool {
+        (self.shape ^ other.shape).count_ones() <= max_distance
+            && self
+                .color
+                .iter()
+                .zip(other.color)
+                .all(|(a, b)| a.abs_diff(b) <= COLOR_TOLERANCE)
+    }
+}
+
+/// Perceptual hash of the visible part of a sprite image.
+///
+/// Transparent borders are trimmed away first and the rest is flattened
+/// onto gray, so the same artwork uploaded with a different canvas size
+/// still hashes alike. Returns `None` for fully transparent images.
+pub fn perceptual_hash(image: &RgbaImage) -> Option<SpriteHash> {
+    let stats = sprite_stats(image);
+    let trimmed = stats.trimmed?;
+    let color = stats.average_color?;
+    let visible = imageops::crop_imm(
+        image,
+        trimmed.x,
+        trimmed.y,
+        trimmed.width,
+        trimmed.height,
+    )
+    .to_image();
+
+    let gray =
+        GrayImage::from_fn(visible.width(), visible.height(), |x, y| {
+            let [r, g, b, a] = visible.get_pixel(x, y).0;
+            let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+            let alpha = a as f64 / 255.0;
+
+            Luma([(luma * alpha + 128.0 * (1.0 - alpha)).round() as u8])
+        });
+    let small = imageops::resize(&gray, 9, 8, FilterType::Triangle);
+
+    let mut shape = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            shape <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                shape |= 1;
+            }
+        }
+    }
+    let channel = |n: Number| (n.0 * 255.0).round() as u8;
+
+    Some(SpriteHash {
+        shape,
+        color: [channel(color.r), channel(color.g), channel(color.b)],
+    })
+}
+
+/// Hashes every decodable `<id>.tga` file in the sprites directory.
+pub fn hash_sprites(
+    sprites_dir: &Path,
+) -> anyhow::Result<BTreeMap<u64, SpriteHash>> {
+    let mut hashes = BTreeMap::new();
+    for (id, path) in sprite_files(sprites_dir)? {
+        if let Some(hash) = load_sprite_image(&path)
+            .ok()
+            .as_ref()
+            .and_then(perceptual_hash)
+        {
+            hashes.insert(id, hash);
+        }
+    }
+    Ok(hashes)
+}
+
+#[derive(Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSprite {
+    pub id: u64,
+    /// Ids of the objects using this sprite.
+    pub objects: Vec<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub sprites: Vec<DuplicateSprite>,
+}
+
+/// Union-find lookup that flattens the path it walks.
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// BK-tree of shape hashes, finding every hash within some number of
+/// differing bits without comparing against all of them.
+#[derive(Debug, Default)]
+struct ShapeTree {
+    /// Each node's hash and its children keyed by distance to it.
+    nodes: Vec<(u64, BTreeMap<u32, usize>)>,
+}
+
+impl ShapeTree {
+    fn insert(&mut self, shape: u64) {
+        let (new, mut current) = (self.nodes.len(), 0);
+        while let Some((node, children)) = self.nodes.get_mut(current) {
+            let distance = (shape ^ *node).count_ones();
+            if distance == 0 {
+                return;
+            }
+            match children.get(&distance) {
+                Some(child) => current = *child,
+                None => {
+                    children.insert(distance, new);
+                    break;
+                }
+            }
+        }
+        self.nodes.push((shape, BTreeMap::new()));
+    }
+
+    fn find(&self, shape: u64, max_distance: u32) -> Vec<u64> {
+        let mut found = vec![];
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(i) = stack.pop() {
+            let (node, children) = &self.nodes[i];
+            let distance = (shape ^ node).count_ones();
+            if distance <= max_distance {
+                found.push(*node);
+            }
+            // Triangle inequality: only children this far from the node
+            // can hold hashes close enough.
+            let from = distance.saturating_sub(max_distance);
+            stack.extend(
+                children
+                    .range(from..=distance + max_distance)
+                    .map(|(_, child)| *child),
+            );
+        }
+        found
+    }
+}
+
+/// Groups sprites whose shape hashes differ in at most `max_distance` bits
+/// and whose colors are close, leaving out sprites that look unique.
+///
+/// Sprites are bucketed by shape hash, and colors are only compared
+/// between buckets whose hashes are close enough. With a `max_distance` of
+/// 0 that is just each bucket, otherwise a BK-tree finds the close ones.
+pub fn find_duplicates(
+    hashes: &BTreeMap<u64, SpriteHash>,
+    objects: &[Object],
+    max_distance: u32,
+) -> Vec<DuplicateGroup> {
+    let ids: Vec<u64> = hashes.keys().copied().collect();
+    let values: Vec<SpriteHash> = hashes.values().copied().collect();
+    let mut parents: Vec<usize> = (0..ids.len()).collect();
+
+    let mut buckets: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for (i, value) in values.iter().enumerate() {
+        buckets.entry(value.shape).or_default().push(i);
+    }
+    let mut tree = ShapeTree::default();
+    if max_distance > 0 {
+        buckets.keys().for_each(|shape| tree.insert(*shape));
+    }
+
+    for (shape, bucket) in &buckets {
+        let close = if max_distance == 0 {
+            vec![*shape]
+        } else {
+            tree.find(*shape, max_distance)
+        };
+
+        // Each pair of buckets is only looked at once.
+        for other in close.into_iter().filter(|other| other >= shape) {
+            for (n, &i) in bucket.iter().enumerate() {
+                let candidates = if other == *shape {
+                    &bucket[n + 1..]
+                } else {
+                    &buckets[&other][..]
+                };
+
+                for &j in candidates {
+                    if values[i].is_similar(&values[j], max_distance) {
+                        let (a, b) = (
+                            find_root(&mut parents, i),
+                            find_root(&mut parents, j),
+                        );
+                        parents[b.max(a)] = a.min(b);
+                    }
+                }
+            }
+        }
+    }
+
+    let index = SpriteIndex::build(objects);
+    let mut groups: BTreeMap<usize, Vec<u64>> = BTreeMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        groups
+            .entry(find_root(&mut parents, i))
+            .or_default()
+            .push(*id);
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| DuplicateGroup {
+            sprites: group
+                .into_iter()
+                .map(|id| DuplicateSprite {
+                    id,
+                    objects: index.object_ids(id),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod find_duplicates_tests {
+    use std::collections::BTreeMap;
+
+    use image::{Rgba, RgbaImage};
+
+    use crate::{
+        duplicates::{
+            find_duplicates, perceptual_hash, DuplicateGroup,
+            DuplicateSprite, SpriteHash,
+        },
+        parser::types::{Object, Sprite},
+    };
+
+    fn gradient(width: u32, height: u32, padding: u32) -> RgbaImage {
+        RgbaImage::from_fn(
+            width + 2 * padding,
+            height + 2 * padding,
+            |x, y| {
+                let inside = x >= padding
+                    && y >= padding
+                    && x < width + padding
+                    && y < height + padding;
+                let v = ((x.saturating_sub(padding)) * 255 / width) as u8;
+
+                if inside {
+                    Rgba([v, v, v, 255])
+                } else {
+                    Rgba([0, 0, 0, 0])
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test() {
+        let mut recolored = gradient(32, 16, 0);
+        recolored.pixels_mut().for_each(|p| p[2] = 0);
+        let hashes = BTreeMap::from([
+            (1, perceptual_hash(&gradient(32, 16, 0)).unwrap()),
+            (2, perceptual_hash(&gradient(32, 16, 5)).unwrap()),
+            (3, perceptual_hash(&recolored).unwrap()),
+        ]);
+        let objects = vec![Object {
+            id: 40,
+            sprites: vec![
+                Sprite {
+                    id: 2,
+                    ..Sprite::default()
+                },
+                Sprite {
+                    id: 3,
+                    ..Sprite::default()
+                },
+            ],
+            ..Object::default()
+        }];
+
+        assert_eq!(
+            find_duplicates(&hashes, &objects, 2),
+            vec![DuplicateGroup {
+                sprites: vec![
+                    DuplicateSprite {
+                        id: 1,
+                        objects: vec![]
+                    },
+                    DuplicateSprite {
+                        id: 2,
+                        objects: vec![40]
+                    },
+                ]
+            }]
+        );
+        assert_eq!(perceptual_hash(&RgbaImage::new(4, 4)), None);
+    }
+
+    #[test]
+    fn test_matches_pairwise() {
+        // Shapes a few bits apart from a handful of bases, some of them
+        // sharing a hash, with colors that don't always match.
+        let mut seed = 7u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            seed >> 33
+        };
+        let bases: Vec<u64> = (0..4).map(|_| next() << 32 | next()).collect();
+        let hashes: BTreeMap<u64, SpriteHash> = (0..60)
+            .map(|id| {
+                let mut shape = bases[next() as usize % bases.len()];
+                for _ in 0..next() % 4 {
+                    shape ^= 1 << (next() % 64);
+                }
+                let color = [0, 0, if next() % 5 == 0 { 100 } else { 0 }];
+
+                (id, SpriteHash { shape, color })
+            })
+            .collect();
+
+        for max_distance in [0, 1, 3, 6] {
+            let values: Vec<(u64, SpriteHash)> =
+                hashes.iter().map(|(id, hash)| (*id, *hash)).collect();
+            let mut groups: Vec<Vec<u64>> = vec![];
+            for (id, hash) in values {
+                // Merge every group holding a similar sprite.
+                let (similar, mut rest): (Vec<_>, Vec<_>) =
+                    groups.into_iter().partition(|group| {
+                        group.iter().any(|other| {
+                            hashes[other].is_similar(&hash, max_distance)
+                        })
+                    });
+                let mut merged: Vec<u64> = similar.concat();
+                merged.push(id);
+                merged.sort();
+                rest.push(merged);
+                groups = rest;
+            }
+            groups.retain(|group| group.len() > 1);
+            groups.sort();
+            assert!(groups.len() > 1);
+
+            let found: Vec<Vec<u64>> =
+                find_duplicates(&hashes, &[], max_distance)
+                    .into_iter()
+                    .map(|group| group.sprites.iter().map(|s| s.id).collect())
+                    .collect();
+            assert_eq!(found, groups, "max distance {}", max_distance);
+        }
+    }
+}
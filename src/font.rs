@@ -0,0 +1,145 @@
+use image::{Rgba, RgbaImage};
+
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+/// Horizontal distance between the start of two consecutive glyphs.
+pub const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// A tiny 3x5 bitmap font, one row per byte with the leftmost pixel in the
+/// highest bit. Lowercase letters are drawn as uppercase.
+const GLYPHS: &[(char, [u8; 5])] = &[
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b011, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+    ('\'', [0b010, 0b010, 0b000, 0b000, 0b000]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('$', [0b011, 0b110, 0b010, 0b011, 0b110]),
+    ('@', [0b111, 0b101, 0b101, 0b100, 0b011]),
+    ('&', [0b010, 0b101, 0b010, 0b101, 0b011]),
+];
+
+fn glyph(c: char) -> [u8; 5] {
+    let c = c.to_ascii_uppercase();
+    let find = |c: char| GLYPHS.iter().find(|(g, _)| *g == c);
+
+    find(c)
+        .or_else(|| find('?'))
+        .map(|(_, rows)| *rows)
+        .unwrap_or_default()
+}
+
+/// Width in pixels `text` takes up when drawn at `scale`.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    (text.chars().count() as u32 * GLYPH_ADVANCE).saturating_sub(1) * scale
+}
+
+/// Draws `text` with its top left corner at `(x, y)`, clipping anything
+/// outside the image.
+pub fn draw_text(
+    image: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    text: &str,
+    color: Rgba<u8>,
+    scale: u32,
+) {
+    for (i, c) in text.chars().enumerate() {
+        let gx = x + i as u32 * GLYPH_ADVANCE * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = gx + col * scale + dx;
+                        let py = y + row as u32 * scale + dy;
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod draw_text_tests {
+    use image::{Rgba, RgbaImage};
+
+    use crate::font::{draw_text, text_width};
+
+    #[test]
+    fn test() {
+        assert_eq!(text_width("AB", 1), 7);
+        assert_eq!(text_width("AB", 2), 14);
+        assert_eq!(text_width("", 1), 0);
+
+        let (black, clear) = (Rgba([0, 0, 0, 255]), Rgba([0, 0, 0, 0]));
+        let draw = |text: &str| {
+            let mut image = RgbaImage::new(8, 5);
+            draw_text(&mut image, 0, 0, text, black, 1);
+            image
+        };
+        let image = draw("L1");
+        assert_eq!(*image.get_pixel(0, 0), black);
+        assert_eq!(*image.get_pixel(1, 0), clear);
+        assert_eq!(*image.get_pixel(2, 4), black);
+        assert_eq!(*image.get_pixel(5, 0), black);
+        assert_eq!(*image.get_pixel(4, 0), clear);
+
+        assert_eq!(draw("abc"), draw("ABC"));
+        assert_eq!(draw("~"), draw("?"));
+        // Text running off the image is clipped.
+        let mut small = RgbaImage::new(2, 2);
+        draw_text(&mut small, 0, 0, "WW", black, 2);
+        assert_eq!(*small.get_pixel(1, 1), black);
+    }
+}
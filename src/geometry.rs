@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap, f64::consts::TAU, fs::File, io::Read, path::Path,
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::types::{BoundingBox, Object, Sprite},
+    sprites::sprite_files,
+    transform::resolve_transforms,
+};
+
+/// A 2D affine transform in object space.
+///
+/// Maps `(x, y)` to `(a * x + c * y + tx, b * x + d * y + ty)`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Affine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Affine {
+    pub const IDENTITY: Self = Self {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        tx: 0.0,
+        ty: 0.0,
+    };
+
+    pub fn translate(x: f64, y: f64) -> Self {
+        Self {
+            tx: x,
+            ty: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Clockwise rotation by `turns` full turns, as stored in `rot`.
+    pub fn rotate(turns: f64) -> Self {
+        let (sin, cos) = (turns * TAU).sin_cos();
+
+        Self {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+
+    pub fn scale(x: f64, y: f64) -> Self {
+        Self {
+            a: x,
+            d: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Returns the transform applying `other` first, then `self`.
+    pub fn then(&self, other: &Affine) -> Self {
+        Self {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            tx: self.a * other.tx + self.c * other.ty + self.tx,
+            ty: self.b * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+
+    /// Returns `None` for transforms that collapse onto a line or point.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+
+        Some(Self {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            tx: (self.c * self.ty - self.d * self.tx) / det,
+            ty: (self.b * self.tx - self.a * self.ty) / det,
+        })
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.tx,
+            self.b * x + self.d * y + self.ty,
+        )
+    }
+}
+
+/// Placement of a sprite in object space, where the game draws it: flipped
+/// around its own center first, then rotated, then moved to `pos`.
+pub fn sprite_local_transform(sprite: &Sprite) -> Affine {
+    let flip = if sprite.h_flip.0 != 0.0 { -1.0 } else { 1.0 };
+
+    Affine::translate(sprite.position.x.0, sprite.position.y.0)
+        .then(&Affine::rotate(sprite.rot.0))
+        .then(&Affine::scale(flip, 1.0))
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Reads the image size from the header of a TGA sprite file.
+pub fn read_tga_size(path: &Path) -> anyhow::Result<SpriteSize> {
+    let mut header = [0u8; 18];
+    File::open(path)?.read_exact(&mut header)?;
+
+    let width = u16::from_le_bytes([header[12], header[13]]);
+    let height = u16::from_le_bytes([header[14], header[15]]);
+    if width == 0 || height == 0 {
+        bail!("{} has an empty TGA image", path.display());
+    }
+
+    Ok(SpriteSize {
+        width: width.into(),
+        height: height.into(),
+    })
+}
+
+/// Collects the size of every `<id>.tga` file in the sprites directory.
+pub fn load_sprite_sizes(
+    sprites_dir: &Path,
+) -> anyhow::Result<HashMap<u64, SpriteSize>> {
+    let mut sizes = HashMap::new();
+    for (id, path) in sprite_files(sprites_dir)? {
+        sizes.insert(id, read_tga_size(&path)?);
+    }
+    Ok(sizes)
+}
+
+/// Computes the extent covered by every sprite of the object whose image
+/// size is known.
+///
+/// Objects whose sprite parents can't be resolved have no bounding box.
+pub fn bounding_box(
+    object: &Object,
+    sizes: &HashMap<u64, SpriteSize>,
+) -> Option<BoundingBox> {
+    let transforms = resolve_transforms(object).ok()?;
+    let mut bounds: Option<BoundingBox> = None;
+
+    for (sprite, world) in object.sprites.iter().zip(transforms) {
+        let Some(size) = sizes.get(&sprite.id) else {
+            continue;
+        };
+        let (hw, hh) = (size.width as f64 / 2.0, size.height as f64 / 2.0);
+
+        for (x, y) in [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)] {
+            let (x, y) = world.matrix.apply(x, y);
+            let b = bounds.get_or_insert(BoundingBox {
+                min_x: x,
+                min_y: y,
+                max_x: x,
+                max_y: y,
+            });
+
+            b.min_x = b.min_x.min(x);
+            b.min_y = b.min_y.min(y);
+            b.max_x = b.max_x.max(x);
+            b.max_y = b.max_y.max(y);
+        }
+    }
+    bounds
+}
+
+/// Fills in `bounding_box` for each object.
+pub fn attach_bounding_boxes(
+    objects: &mut [Object],
+    sizes: &HashMap<u64, SpriteSize>,
+) {
+    for object in objects {
+        object.bounding_box = bounding_box(object, sizes);
+    }
+}
+
+#[cfg(test)]
+mod bounding_box_tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        geometry::{bounding_box, SpriteSize},
+        parser::types::{BoundingBox, Number, Object, Position, Sprite},
+    };
+
+    fn sprite(id: u64, x: f64, y: f64, rot: f64, parent: i64) -> Sprite {
+        Sprite {
+            id,
+            position: Position {
+                x: Number(x),
+                y: Number(y),
+            },
+            rot: Number(rot),
+            parent,
+            ..Sprite::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        let object = Object {
+            sprites: vec![
+                sprite(1, 10.0, 0.0, 0.0, -1),
+                // Quarter turn swaps width and height. Its `pos` is in
+                // object space like the game draws it, so the parent
+                // doesn't move it.
+                sprite(2, 10.0, 20.0, 0.25, 0),
+                // No known size, ignored.
+                sprite(3, 500.0, 500.0, 0.0, -1),
+            ],
+            ..Object::default()
+        };
+        let sizes = HashMap::from([
+            (
+                1,
+                SpriteSize {
+                    width: 4,
+                    height: 2,
+                },
+            ),
+            (
+                2,
+                SpriteSize {
+                    width: 6,
+                    height: 2,
+                },
+            ),
+        ]);
+        let b = bounding_box(&object, &sizes).unwrap();
+
+        assert!((b.min_x - 8.0).abs() < 1e-9);
+        assert!((b.max_x - 12.0).abs() < 1e-9);
+        assert!((b.min_y - -1.0).abs() < 1e-9);
+        assert!((b.max_y - 23.0).abs() < 1e-9);
+        assert_eq!(
+            bounding_box(&Object::default(), &sizes),
+            None::<BoundingBox>
+        );
+
+        let looped = Object {
+            sprites: vec![
+                sprite(1, 0.0, 0.0, 0.0, 1),
+                sprite(2, 0.0, 0.0, 0.0, 0),
+            ],
+            ..Object::default()
+        };
+        assert_eq!(bounding_box(&looped, &sizes), None::<BoundingBox>);
+    }
+}
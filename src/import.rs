@@ -0,0 +1,210 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::{LineContent, ObjectDocument},
+    parser::types::Object,
+    writer::to_object_text,
+};
+
+pub const NEXT_OBJECT_NUMBER_FILE: &str = "nextObjectNumber.txt";
+
+/// A new object file as the game's editor saves it, with placeholders for
+/// the keys `to_object_text` writes. The sprites and index lists go before
+/// `pixHeight`.
+const OBJECT_TEMPLATE: &str = "id=0
+
+containable=0
+containSize=1.000000,vertSlotRot=0.000000
+permanent=0,minPickupAge=3
+noFlip=0
+sideAccess=0
+heldInHand=0
+blocksWalking=0,leftBlockingRadius=0,rightBlockingRadius=0,drawBehindPlayer=0
+mapChance=0.000000#biomes_0
+heatValue=0
+rValue=0.000000
+person=0,noSpawn=0
+male=0
+deathMarker=0
+homeMarker=0
+floor=0
+floorHugging=0
+foodValue=0
+speedMult=1.000000
+heldOffset=0.000000,0.000000
+clothing=n
+clothingOffset=0.000000,0.000000
+deadlyDistance=0
+useDistance=1
+sounds=-1:0.250000,-1:0.250000,-1:0.250000,-1:0.250000
+creationSoundInitialOnly=0
+creationSoundForce=0
+numSlots=0#timeStretch=1.000000
+slotSize=1.000000
+slotsLocked=0
+slotsNoSwap=0
+pixHeight=0";
+
+/// The complete object file for the object: the template's defaults for
+/// every key `parse_object` skips, overlaid with the object's own fields.
+pub fn object_file_document(object: &Object) -> ObjectDocument {
+    let mut document = ObjectDocument::parse(OBJECT_TEMPLATE);
+    let mut written = ObjectDocument::parse(&to_object_text(object));
+
+    document.set_description(&object.description);
+    for field in written.fields() {
+        if let Some(template_field) = document.field_mut(&field.key) {
+            template_field.set_raw(field.raw());
+        }
+    }
+
+    let sprites_start = written
+        .lines
+        .iter()
+        .position(|line| {
+            matches!(&line.content, LineContent::Fields(fields)
+                if fields.iter().any(|f| f.key == "numSprites"))
+        })
+        .unwrap_or(written.lines.len());
+    let mut sprite_lines = written.lines.split_off(sprites_start);
+    if let Some(last) = sprite_lines.last_mut() {
+        last.ending = "\n".to_string();
+    }
+    let pix_height = document.lines.len() - 1;
+    document.lines.splice(pix_height..pix_height, sprite_lines);
+
+    document
+}
+
+pub fn read_next_object_number(objects_dir: &Path) -> anyhow::Result<u64> {
+    let path = objects_dir.join(NEXT_OBJECT_NUMBER_FILE);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("can't read {}", path.display()))?;
+
+    content.trim().parse().with_context(|| {
+        format!("invalid object number in {}", path.display())
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedObject {
+    /// Id the object had in the imported JSON.
+    pub source_id: u64,
+    pub id: u64,
+}
+
+/// Writes the objects as new `<id>.txt` files, numbering them from the
+/// directory's `nextObjectNumber.txt` and moving that counter past them.
+///
+/// Nothing is written if any of the files would overwrite an existing one.
+pub fn import_objects(
+    objects: &[Object],
+    objects_dir: &Path,
+) -> anyhow::Result<Vec<ImportedObject>> {
+    let first_id = read_next_object_number(objects_dir)?;
+    let imported: Vec<ImportedObject> = objects
+        .iter()
+        .zip(first_id..)
+        .map(|(object, id)| ImportedObject {
+            source_id: object.id,
+            id,
+        })
+        .collect();
+
+    for ImportedObject { id, .. } in &imported {
+        let path = objects_dir.join(format!("{}.txt", id));
+        if path.exists() {
+            bail!("{} already exists", path.display());
+        }
+    }
+
+    for (object, ImportedObject { id, .. }) in objects.iter().zip(&imported) {
+        let object = Object {
+            id: *id,
+            ..object.clone()
+        };
+        let path = objects_dir.join(format!("{}.txt", id));
+        fs::write(path, object_file_document(&object).to_text())?;
+    }
+
+    let next_id = first_id + imported.len() as u64;
+    fs::write(
+        objects_dir.join(NEXT_OBJECT_NUMBER_FILE),
+        next_id.to_string(),
+    )?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod import_objects_tests {
+    use std::{env, fs, process};
+
+    use crate::{
+        import::{import_objects, read_next_object_number, ImportedObject},
+        parser::{parse, types::Object},
+    };
+
+    #[test]
+    fn test() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_import_objects_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("nextObjectNumber.txt"), "500").unwrap();
+
+        let object = |id: u64, description: &str| Object {
+            id,
+            description: description.to_string(),
+            head_index: vec![-1],
+            body_index: vec![-1],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            num_uses: 1,
+            ..Object::default()
+        };
+        let imported = import_objects(
+            &[object(7, "Stone"), object(7, "Sharp Stone")],
+            &dir,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported,
+            vec![
+                ImportedObject {
+                    source_id: 7,
+                    id: 500
+                },
+                ImportedObject {
+                    source_id: 7,
+                    id: 501
+                }
+            ]
+        );
+        assert_eq!(read_next_object_number(&dir).unwrap(), 502);
+
+        let parsed = parse(&dir).unwrap();
+        assert_eq!(
+            parsed,
+            vec![object(500, "Stone"), object(501, "Sharp Stone")]
+        );
+
+        let text = fs::read_to_string(dir.join("500.txt")).unwrap();
+        for key in ["containable=0", "heldInHand=0", "mapChance=", "sounds="]
+        {
+            assert!(text.contains(key), "{} missing", key);
+        }
+        assert!(text.starts_with("id=500\nStone\ncontainable=0\n"));
+        assert!(text.ends_with("frontFootIndex=-1\npixHeight=0"));
+
+        fs::write(dir.join("nextObjectNumber.txt"), "501").unwrap();
+        assert!(import_objects(&[object(1, "Flint")], &dir).is_err());
+        assert_eq!(read_next_object_number(&dir).unwrap(), 501);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
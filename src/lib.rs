@@ -1 +1,26 @@
+pub mod animation;
+pub mod cache;
+pub mod contact_sheet;
+pub mod description;
+pub mod diff;
+pub mod document;
+pub mod duplicates;
+pub mod font;
+pub mod geometry;
+pub mod import;
+pub mod lint;
+pub mod object_ref;
 pub mod parser;
+pub mod patch;
+pub mod query;
+pub mod remap;
+pub mod render;
+pub mod search;
+pub mod sprite_diff;
+pub mod sprite_index;
+pub mod sprites;
+pub mod stats;
+pub mod transform;
+pub mod unused;
+pub mod validate;
+pub mod writer;
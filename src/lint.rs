@@ -0,0 +1,337 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::types::{
+        ClothingObject, NonPersonObject, Number, Object, ObjectKind, Position,
+    },
+    validate::validate,
+};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The rule doesn't run.
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Off => "off",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Rule {
+    /// The checks of `validate::validate` on sprite index fields.
+    SpriteIndices,
+    /// Sprite color of `0,0,0`, which multiplies the sprite to black.
+    ZeroTint,
+    /// Sprite positions further from the origin than `maxCoordinate`.
+    CoordinateRange,
+    /// Sprite age ranges starting after they end.
+    AgeRange,
+    /// Clothing worn exactly at the origin, usually a forgotten offset.
+    ClothingOffset,
+    /// Objects sharing their description with another object.
+    DuplicateDescription,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::SpriteIndices,
+        Rule::ZeroTint,
+        Rule::CoordinateRange,
+        Rule::AgeRange,
+        Rule::ClothingOffset,
+        Rule::DuplicateDescription,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SpriteIndices => "spriteIndices",
+            Self::ZeroTint => "zeroTint",
+            Self::CoordinateRange => "coordinateRange",
+            Self::AgeRange => "ageRange",
+            Self::ClothingOffset => "clothingOffset",
+            Self::DuplicateDescription => "duplicateDescription",
+        }
+    }
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Self::SpriteIndices | Self::AgeRange => Severity::Error,
+            Self::ZeroTint | Self::CoordinateRange => Severity::Warning,
+            Self::ClothingOffset | Self::DuplicateDescription => {
+                Severity::Info
+            }
+        }
+    }
+}
+
+/// Read from a JSON file such as
+/// `{ "rules": { "zeroTint": "error", "duplicateDescription": "off" },
+/// "maxCoordinate": 500 }`. Rules left out keep their default severity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub rules: BTreeMap<Rule, Severity>,
+    pub max_coordinate: f64,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            rules: BTreeMap::new(),
+            max_coordinate: 1000.0,
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or(rule.default_severity())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintMessage {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub object_id: u64,
+    pub message: String,
+}
+
+impl fmt::Display for LintMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity,
+            self.rule.name(),
+            self.object_id,
+            self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    pub messages: Vec<LintMessage>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+}
+
+fn is_origin(position: &Position) -> bool {
+    position.x == Number(0.0) && position.y == Number(0.0)
+}
+
+/// Messages of one rule for one object, without severity.
+/// `by_description` holds the ids of every object with each description.
+fn check_object(
+    rule: Rule,
+    object: &Object,
+    config: &LintConfig,
+    by_description: &BTreeMap<&str, Vec<u64>>,
+) -> Vec<String> {
+    let sprites = object.sprites.iter().enumerate();
+
+    match rule {
+        Rule::SpriteIndices => validate(object)
+            .iter()
+            .map(|d| format!("{}: {}", d.field, d.problem))
+            .collect(),
+        Rule::ZeroTint => sprites
+            .filter(|(_, s)| {
+                [&s.color.r, &s.color.g, &s.color.b]
+                    .iter()
+                    .all(|c| c.0 == 0.0)
+            })
+            .map(|(i, s)| format!("sprite {} ({}) is tinted black", i, s.id))
+            .collect(),
+        Rule::CoordinateRange => sprites
+            .filter(|(_, s)| {
+                s.position.x.0.abs() > config.max_coordinate
+                    || s.position.y.0.abs() > config.max_coordinate
+            })
+            .map(|(i, s)| {
+                format!(
+                    "sprite {} ({}) is at ({}, {}), beyond {}",
+                    i,
+                    s.id,
+                    s.position.x.0,
+                    s.position.y.0,
+                    config.max_coordinate
+                )
+            })
+            .collect(),
+        Rule::AgeRange => sprites
+            .filter(|(_, s)| s.age_range.min.0 > s.age_range.max.0)
+            .map(|(i, s)| {
+                format!(
+                    "sprite {} ({}) has age range {} to {}",
+                    i, s.id, s.age_range.min.0, s.age_range.max.0
+                )
+            })
+            .collect(),
+        Rule::ClothingOffset => match &object.kind {
+            ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Shoe(offset)
+                | ClothingObject::Tunic(offset)
+                | ClothingObject::Hat(offset)
+                | ClothingObject::Bottom(offset)
+                | ClothingObject::Backpack(offset),
+            )) if is_origin(offset) => {
+                vec!["clothing offset is 0,0".to_string()]
+            }
+            _ => vec![],
+        },
+        Rule::DuplicateDescription => {
+            let others: Vec<String> = by_description
+                .get(object.description.as_str())
+                .into_iter()
+                .flatten()
+                .filter(|&&id| id != object.id)
+                .map(u64::to_string)
+                .collect();
+            if others.is_empty() {
+                vec![]
+            } else {
+                vec![format!(
+                    "description is also used by {}",
+                    others.join(", ")
+                )]
+            }
+        }
+    }
+}
+
+/// Runs every enabled rule over the objects, reporting in object order.
+pub fn lint(objects: &[Object], config: &LintConfig) -> LintReport {
+    let mut messages = vec![];
+
+    let mut by_description: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for object in objects {
+        by_description
+            .entry(&object.description)
+            .or_default()
+            .push(object.id);
+    }
+
+    for object in objects {
+        for rule in Rule::ALL {
+            let severity = config.severity(rule);
+            if severity == Severity::Off {
+                continue;
+            }
+
+            let found = check_object(rule, object, config, &by_description);
+            messages.extend(found.into_iter().map(|message| LintMessage {
+                rule,
+                severity,
+                object_id: object.id,
+                message,
+            }));
+        }
+    }
+
+    let count = |severity| {
+        messages
+            .iter()
+            .filter(|m: &&LintMessage| m.severity == severity)
+            .count()
+    };
+    LintReport {
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+        infos: count(Severity::Info),
+        messages,
+    }
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use crate::{
+        lint::{lint, LintConfig, Rule, Severity},
+        parser::types::{AgeRange, ColorRGB, Number, Object, Sprite},
+    };
+
+    #[test]
+    fn test_lint() {
+        let object = |id, description: &str| Object {
+            id,
+            description: description.to_string(),
+            sprites: vec![Sprite {
+                color: ColorRGB::default(),
+                age_range: AgeRange {
+                    min: Number(20.0),
+                    max: Number(10.0),
+                },
+                ..Sprite::default()
+            }],
+            head_index: vec![-1],
+            ..Object::default()
+        };
+        let objects = [object(1, "Stone"), object(2, "Stone")];
+
+        let report = lint(&objects, &LintConfig::default());
+        let rules: Vec<Rule> =
+            report.messages.iter().map(|m| m.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                Rule::ZeroTint,
+                Rule::AgeRange,
+                Rule::DuplicateDescription,
+                Rule::ZeroTint,
+                Rule::AgeRange,
+                Rule::DuplicateDescription,
+            ]
+        );
+        assert_eq!((report.errors, report.warnings, report.infos), (2, 2, 2));
+        assert_eq!(
+            report.messages[2].to_string(),
+            "info[duplicateDescription] 1: description is also used by 2"
+        );
+
+        let config: LintConfig = serde_json::from_str(
+            r#"{ "rules": { "ageRange": "off", "zeroTint": "error" } }"#,
+        )
+        .unwrap();
+        assert_eq!(config.severity(Rule::ZeroTint), Severity::Error);
+        let report = lint(&objects[..1], &config);
+        assert_eq!(report.messages.len(), 1);
+        assert!(report.has_errors());
+        assert!(serde_json::from_str::<LintConfig>(
+            r#"{ "rules": { "x": "off" } }"#
+        )
+        .is_err());
+    }
+}
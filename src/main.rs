@@ -1,31 +1,582 @@
-use std::{env, path::PathBuf, process::exit};
-use thol_sprites_mini_parser::parser::{parse, types::Object};
+use anyhow::{bail, Context};
+use serde::Serializer;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::exit,
+    slice,
+};
+use thol_sprites_mini_parser::{
+    animation::{render_use_states, write_animation, AnimationFormat},
+    cache::parse_cached,
+    contact_sheet::{write_contact_sheets, ContactSheetOptions},
+    description::DescribedObject,
+    diff::{diff_releases, Release},
+    document::ObjectDocument,
+    duplicates::{find_duplicates, hash_sprites},
+    geometry::{attach_bounding_boxes, load_sprite_sizes},
+    import::import_objects,
+    lint::{lint as lint_objects, LintConfig},
+    parser::{
+        file_names_match_ids, object_files, ordered_object_files, parse,
+        parse_ordered, types::Object, ObjectOrder, ObjectReader, ParseError,
+    },
+    patch::{apply_to_document, apply_to_object, line_diff, Patch},
+    query::Query,
+    remap::{missing_targets, remap_document_sprites, SpriteMapping},
+    search::SearchIndex,
+    sprite_index::SpriteIndex,
+    sprites::{sprite_files, SpriteImages},
+    stats::{collect_sprite_stats, sprite_stats_csv},
+    unused::{sprite_reference_report, UnparsedFile},
+};
 
 fn main() -> anyhow::Result<()> {
     let mut args = env::args();
     args.next();
-    let objects_dir = args.next().map(|x| PathBuf::from(&x));
 
-    match objects_dir {
-        Some(path) => {
-            if path.is_dir() {
-                let objects = parse(&path)?;
-                let objects_str =
-                    serde_json::to_string_pretty::<Vec<Object>>(&objects)?;
-
-                print!("{}", objects_str);
-            } else {
-                eprintln!(
-                    "{} is an invalid objects directory",
-                    path.display()
-                );
-                exit(1);
-            }
+    match args.next().as_deref() {
+        Some("contact-sheet") => contact_sheet(args.collect()),
+        Some("use-states") => use_states(args.collect()),
+        Some("sprite-stats") => sprite_stats(args.collect()),
+        Some("duplicates") => duplicates(args.collect()),
+        Some("patch") => patch(args.collect()),
+        Some("import") => import(args.collect()),
+        Some("remap-sprites") => remap_sprites(args.collect()),
+        Some("who-uses") => who_uses(args.collect()),
+        Some("unused-sprites") => unused_sprites(args.collect()),
+        Some("query") => query(args.collect()),
+        Some("search") => search(args.collect()),
+        Some("diff") => diff(args.collect()),
+        Some("lint") => lint(args.collect()),
+        Some(objects_dir) => {
+            dump([objects_dir.to_string()].into_iter().chain(args).collect())
         }
         None => {
             eprintln!("Need THOL objects directory path as argument");
             exit(1)
         }
     }
+}
+
+/// `tsmp <objects dir> [sprites dir] [--order id|description|file]
+/// [--cache-dir <dir>]`
+fn dump(mut args: Vec<String>) -> anyhow::Result<()> {
+    let order = take_order_flag(&mut args)?;
+    let cache_dir = take_flag(&mut args, "--cache-dir");
+    let mut args = args.into_iter();
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = args.next();
+
+    let sizes = match sprites_dir {
+        Some(dir) => Some(load_sprite_sizes(&sprites_dir_arg(Some(dir)))?),
+        None => None,
+    };
+    let with_bounding_box = |mut object: Object| {
+        if let Some(sizes) = &sizes {
+            attach_bounding_boxes(slice::from_mut(&mut object), sizes);
+        }
+        object
+    };
+
+    let stdout = io::stdout().lock();
+    if let Some(cache_dir) = cache_dir {
+        let (objects, _) =
+            parse_cached(&objects_dir, &PathBuf::from(cache_dir), order)?;
+        write_json_array(stdout, objects.into_iter().map(with_bounding_box))
+    } else {
+        // In file order, and in id order when every file is named after its
+        // id, objects are written as they are read. Otherwise they are
+        // collected and sorted first.
+        let files = ordered_object_files(&objects_dir, order)?;
+        if order == ObjectOrder::Description
+            || (order == ObjectOrder::Id && !file_names_match_ids(&files)?)
+        {
+            let objects = parse_ordered(&objects_dir, order)?;
+            return write_json_array(
+                stdout,
+                objects.into_iter().map(with_bounding_box),
+            );
+        }
+
+        let objects = ObjectReader::from_files(files).filter_map(|object| {
+            object
+                .map_err(|e| eprintln!("Skipping {}", e))
+                .ok()
+                .map(with_bounding_box)
+        });
+        write_json_array(stdout, objects)
+    }
+}
+
+/// Writes the objects with their description parts as a pretty JSON array
+/// one at a time, the same as `serde_json::to_string_pretty` would write a
+/// `Vec` of them.
+fn write_json_array(
+    out: impl io::Write,
+    objects: impl Iterator<Item = Object>,
+) -> anyhow::Result<()> {
+    serde_json::Serializer::pretty(out)
+        .collect_seq(objects.map(DescribedObject::from))?;
+    Ok(())
+}
+
+/// `tsmp contact-sheet <objects dir> <sprites dir> <output dir>
+/// [--match <text>] [--ids <id,...>] [--columns <n>] [--rows <n>]`
+fn contact_sheet(mut args: Vec<String>) -> anyhow::Result<()> {
+    let description_match = take_flag(&mut args, "--match");
+    let ids = take_ids_flag(&mut args)?;
+    let mut options = ContactSheetOptions::default();
+    if let Some(columns) = take_flag(&mut args, "--columns") {
+        options.columns = columns.parse()?;
+    }
+    if let Some(rows) = take_flag(&mut args, "--rows") {
+        options.rows = rows.parse()?;
+    }
+
+    let mut args = args.into_iter();
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = sprites_dir_arg(args.next());
+    let Some(out_dir) = args.next().map(PathBuf::from) else {
+        eprintln!("Need an output directory path for the contact sheets");
+        exit(1)
+    };
+
+    let objects = parse(&objects_dir)?;
+    let selected: Vec<&Object> = objects
+        .iter()
+        .filter(|o| ids.as_ref().is_none_or(|ids| ids.contains(&o.id)))
+        .filter(|o| {
+            description_match.as_ref().is_none_or(|m| {
+                o.description.to_lowercase().contains(&m.to_lowercase())
+            })
+        })
+        .collect();
+
+    let mut images = SpriteImages::new(&sprites_dir);
+    for path in
+        write_contact_sheets(&selected, &mut images, &options, &out_dir)?
+    {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// `tsmp use-states <objects dir> <sprites dir> <output dir>
+/// [--ids <id,...>] [--format gif|apng] [--delay <ms>]`
+fn use_states(mut args: Vec<String>) -> anyhow::Result<()> {
+    let ids = take_ids_flag(&mut args)?;
+    let format = match take_flag(&mut args, "--format").as_deref() {
+        None | Some("gif") => AnimationFormat::Gif,
+        Some("apng") => AnimationFormat::Apng,
+        Some(other) => {
+            eprintln!("{} is not a supported animation format", other);
+            exit(1)
+        }
+    };
+    let delay = take_flag(&mut args, "--delay")
+        .map(|d| d.parse::<u32>())
+        .transpose()?
+        .unwrap_or(500);
+
+    let mut args = args.into_iter();
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = sprites_dir_arg(args.next());
+    let Some(out_dir) = args.next().map(PathBuf::from) else {
+        eprintln!("Need an output directory path for the animations");
+        exit(1)
+    };
+    fs::create_dir_all(&out_dir)?;
+
+    let mut images = SpriteImages::new(&sprites_dir);
+    for object in parse(&objects_dir)? {
+        if object.num_uses <= 1
+            || ids.as_ref().is_some_and(|ids| !ids.contains(&object.id))
+        {
+            continue;
+        }
+
+        let path =
+            out_dir.join(format!("{}.{}", object.id, format.extension()));
+        match render_use_states(&object, &mut images) {
+            Ok(frames) => {
+                write_animation(&frames, &path, format, delay)?;
+                println!("{}", path.display());
+            }
+            Err(e) => eprintln!("Skipping object {}: {}", object.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// `tsmp sprite-stats <sprites dir> [--format json|csv] [--ids <id,...>]`
+fn sprite_stats(mut args: Vec<String>) -> anyhow::Result<()> {
+    let ids = take_ids_flag(&mut args)?;
+    let format = take_flag(&mut args, "--format");
+    let sprites_dir = sprites_dir_arg(args.into_iter().next());
+
+    let mut stats = collect_sprite_stats(&sprites_dir)?;
+    if let Some(ids) = ids {
+        stats.retain(|id, _| ids.contains(id));
+    }
+
+    match format.as_deref() {
+        None | Some("json") => {
+            print!("{}", serde_json::to_string_pretty(&stats)?)
+        }
+        Some("csv") => print!("{}", sprite_stats_csv(&stats)),
+        Some(other) => {
+            eprintln!("{} is not a supported output format", other);
+            exit(1)
+        }
+    }
+    Ok(())
+}
+
+/// `tsmp duplicates <objects dir> <sprites dir> [--distance <bits>]`
+fn duplicates(mut args: Vec<String>) -> anyhow::Result<()> {
+    let max_distance = take_flag(&mut args, "--distance")
+        .map(|d| d.parse::<u32>())
+        .transpose()?
+        .unwrap_or(0);
+    let mut args = args.into_iter();
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = sprites_dir_arg(args.next());
+
+    let objects = parse(&objects_dir)?;
+    let hashes = hash_sprites(&sprites_dir)?;
+    let groups = find_duplicates(&hashes, &objects, max_distance);
+    print!("{}", serde_json::to_string_pretty(&groups)?);
+
+    Ok(())
+}
+
+/// `tsmp patch <patch file> <objects dir> [--dry-run]`
+fn patch(mut args: Vec<String>) -> anyhow::Result<()> {
+    let dry_run = take_switch(&mut args, "--dry-run");
+    let mut args = args.into_iter();
+    let Some(patch_path) = args.next().map(PathBuf::from) else {
+        eprintln!("Need a JSON patch file path as argument");
+        exit(1)
+    };
+    let objects_dir = objects_dir_arg(args.next());
+    let patch: Patch =
+        serde_json::from_str(&fs::read_to_string(&patch_path)?)
+            .with_context(|| {
+                format!("invalid patch {}", patch_path.display())
+            })?;
+
+    // Check every change applies before writing anything.
+    let mut updates = vec![];
+    for (id, changes) in &patch {
+        changes
+            .validate()
+            .with_context(|| format!("invalid patch for object {}", id))?;
+        let path = objects_dir.join(format!("{}.txt", id));
+        let old = fs::read_to_string(&path)
+            .with_context(|| format!("can't read object {}", id))?;
+
+        let mut document = ObjectDocument::parse(&old);
+        let mut object = document.to_object()?;
+        apply_to_document(&mut document, changes)
+            .and_then(|_| apply_to_object(&mut object, changes))
+            .with_context(|| format!("can't patch object {}", id))?;
+        if document.to_object()? != object {
+            bail!("patched object {} doesn't read back as expected", id);
+        }
+
+        updates.push((path, old, document.to_text()));
+    }
+
+    for (path, old, new) in updates {
+        if dry_run {
+            print!("{}", line_diff(&path.display().to_string(), &old, &new));
+        } else if old != new {
+            fs::write(&path, new)?;
+            println!("{}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// `tsmp import <objects.json> --out <objects dir>`
+fn import(mut args: Vec<String>) -> anyhow::Result<()> {
+    let objects_dir = objects_dir_arg(take_flag(&mut args, "--out"));
+    let Some(json_path) = args.into_iter().next().map(PathBuf::from) else {
+        eprintln!("Need a JSON objects file path as argument");
+        exit(1)
+    };
+    let objects: Vec<Object> = serde_json::from_str(&fs::read_to_string(
+        &json_path,
+    )?)
+    .with_context(|| format!("invalid objects {}", json_path.display()))?;
+
+    for imported in import_objects(&objects, &objects_dir)? {
+        println!(
+            "{} -> {}",
+            imported.source_id,
+            objects_dir.join(format!("{}.txt", imported.id)).display()
+        );
+    }
     Ok(())
 }
+
+/// `tsmp remap-sprites <mapping.json> <objects dir> <sprites dir>
+/// [--dry-run]`
+fn remap_sprites(mut args: Vec<String>) -> anyhow::Result<()> {
+    let dry_run = take_switch(&mut args, "--dry-run");
+    let mut args = args.into_iter();
+    let Some(mapping_path) = args.next().map(PathBuf::from) else {
+        eprintln!("Need a JSON sprite mapping file path as argument");
+        exit(1)
+    };
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = sprites_dir_arg(args.next());
+    let mapping: SpriteMapping =
+        serde_json::from_str(&fs::read_to_string(&mapping_path)?)
+            .with_context(|| {
+                format!("invalid mapping {}", mapping_path.display())
+            })?;
+
+    let existing = sprite_files(&sprites_dir)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let missing = missing_targets(&mapping, &existing);
+    if !missing.is_empty() {
+        bail!("refusing to remap onto missing sprites {:?}", missing);
+    }
+
+    // Remap every object before writing anything, so an unreadable file
+    // leaves them all as they were.
+    let mut updates = vec![];
+    for path in object_files(&objects_dir)? {
+        let old = fs::read_to_string(&path)?;
+        let mut document = ObjectDocument::parse(&old);
+        if remap_document_sprites(&mut document, &mapping) {
+            let new = document.to_text();
+            updates.push((path, old, new));
+        }
+    }
+
+    if dry_run {
+        for (path, old, new) in updates {
+            print!("{}", line_diff(&path.display().to_string(), &old, &new));
+        }
+        return Ok(());
+    }
+
+    // Each file is written next to itself first and only renamed over the
+    // original once all of them are written, so none is left half written.
+    let temp_path = |path: &Path| path.with_extension("txt.tmp");
+    for (path, _, new) in &updates {
+        if let Err(e) = fs::write(temp_path(path), new) {
+            for (path, _, _) in &updates {
+                let _ = fs::remove_file(temp_path(path));
+            }
+            return Err(e).with_context(|| {
+                format!("can't write {}", temp_path(path).display())
+            });
+        }
+    }
+    for (path, _, _) in updates {
+        fs::rename(temp_path(&path), &path)?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// `tsmp who-uses <sprite id> <objects dir>`
+fn who_uses(args: Vec<String>) -> anyhow::Result<()> {
+    let mut args = args.into_iter();
+    let Some(sprite_id) = args.next() else {
+        eprintln!("Need a sprite id as argument");
+        exit(1)
+    };
+    let sprite_id: u64 = sprite_id
+        .parse()
+        .with_context(|| format!("{} is an invalid sprite id", sprite_id))?;
+    let objects_dir = objects_dir_arg(args.next());
+
+    let index = SpriteIndex::build(&parse(&objects_dir)?);
+    print!("{}", serde_json::to_string_pretty(index.usages(sprite_id))?);
+
+    Ok(())
+}
+
+/// `tsmp unused-sprites <objects dir> <sprites dir>`
+fn unused_sprites(args: Vec<String>) -> anyhow::Result<()> {
+    let mut args = args.into_iter();
+    let objects_dir = objects_dir_arg(args.next());
+    let sprites_dir = sprites_dir_arg(args.next());
+
+    let mut objects = vec![];
+    let mut unparsed = vec![];
+    for object in ObjectReader::new(&objects_dir)? {
+        match object {
+            Ok(object) => objects.push(object),
+            Err(ParseError::Syntax { path, .. }) => {
+                unparsed.push(UnparsedFile::read(&path)?)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let index = SpriteIndex::build(&objects);
+    let files = sprite_files(&sprites_dir)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let report = sprite_reference_report(&index, unparsed, &files);
+    print!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
+
+/// `tsmp query '<expression>' <objects dir> [--order id|description|file]`
+fn query(mut args: Vec<String>) -> anyhow::Result<()> {
+    let order = take_order_flag(&mut args)?;
+    let mut args = args.into_iter();
+    let Some(expression) = args.next() else {
+        eprintln!("Need a query expression as argument");
+        exit(1)
+    };
+    let query = Query::parse(&expression)?;
+    let objects_dir = objects_dir_arg(args.next());
+
+    let objects = parse_ordered(&objects_dir, order)?
+        .into_iter()
+        .filter(|o| query.matches(o));
+    write_json_array(io::stdout().lock(), objects)?;
+
+    Ok(())
+}
+
+/// `tsmp search '<terms>' <objects dir>`
+fn search(args: Vec<String>) -> anyhow::Result<()> {
+    let mut args = args.into_iter();
+    let Some(terms) = args.next() else {
+        eprintln!("Need search terms as argument");
+        exit(1)
+    };
+    let objects_dir = objects_dir_arg(args.next());
+
+    let index = SearchIndex::build(&parse(&objects_dir)?);
+    print!("{}", serde_json::to_string_pretty(&index.search(&terms))?);
+
+    Ok(())
+}
+
+/// `tsmp diff <old objects dir> <new objects dir> [--format text|json]`
+fn diff(mut args: Vec<String>) -> anyhow::Result<()> {
+    let format = take_flag(&mut args, "--format");
+    let mut args = args.into_iter();
+    let old_dir = objects_dir_arg(args.next());
+    let new_dir = objects_dir_arg(args.next());
+
+    let diff =
+        diff_releases(&Release::read(&old_dir)?, &Release::read(&new_dir)?);
+    match format.as_deref() {
+        None | Some("text") => print!("{}", diff),
+        Some("json") => print!("{}", serde_json::to_string_pretty(&diff)?),
+        Some(other) => bail!("unknown format {}", other),
+    }
+
+    Ok(())
+}
+
+/// `tsmp lint <objects dir> [--config <config.json>] [--format text|json]`
+fn lint(mut args: Vec<String>) -> anyhow::Result<()> {
+    let format = take_flag(&mut args, "--format");
+    let config = match take_flag(&mut args, "--config") {
+        Some(path) => serde_json::from_str(&fs::read_to_string(&path)?)
+            .with_context(|| format!("invalid lint config {}", path))?,
+        None => LintConfig::default(),
+    };
+    let objects_dir = objects_dir_arg(args.into_iter().next());
+
+    let report = lint_objects(&parse(&objects_dir)?, &config);
+
+    match format.as_deref() {
+        None | Some("text") => {
+            for message in &report.messages {
+                println!("{}", message);
+            }
+        }
+        Some("json") => print!("{}", serde_json::to_string_pretty(&report)?),
+        Some(other) => bail!("unknown format {}", other),
+    }
+
+    if report.has_errors() {
+        exit(1)
+    }
+    Ok(())
+}
+
+fn take_order_flag(args: &mut Vec<String>) -> anyhow::Result<ObjectOrder> {
+    match take_flag(args, "--order").as_deref() {
+        None | Some("id") => Ok(ObjectOrder::Id),
+        Some("description") => Ok(ObjectOrder::Description),
+        Some("file") => Ok(ObjectOrder::File),
+        Some(other) => bail!("unknown order {}", other),
+    }
+}
+
+fn take_ids_flag(args: &mut Vec<String>) -> anyhow::Result<Option<Vec<u64>>> {
+    let ids = take_flag(args, "--ids")
+        .map(|ids| {
+            ids.split(',')
+                .map(|id| id.trim().parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+
+    Ok(ids)
+}
+
+/// Removes `name <value>` from the arguments, returning the value.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    if i + 1 >= args.len() {
+        eprintln!("{} needs a value", name);
+        exit(1);
+    }
+    args.remove(i);
+
+    Some(args.remove(i))
+}
+
+/// Removes `name` from the arguments, returning whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    let found = args.iter().any(|a| a == name);
+    args.retain(|a| a != name);
+
+    found
+}
+
+fn objects_dir_arg(arg: Option<String>) -> PathBuf {
+    match arg.map(PathBuf::from) {
+        Some(path) if path.is_dir() => path,
+        Some(path) => {
+            eprintln!("{} is an invalid objects directory", path.display());
+            exit(1)
+        }
+        None => {
+            eprintln!("Need THOL objects directory path as argument");
+            exit(1)
+        }
+    }
+}
+
+fn sprites_dir_arg(arg: Option<String>) -> PathBuf {
+    match arg.map(PathBuf::from) {
+        Some(path) if path.is_dir() => path,
+        Some(path) => {
+            eprintln!("{} is an invalid sprites directory", path.display());
+            exit(1)
+        }
+        None => {
+            eprintln!("Need THOL sprites directory path as argument");
+            exit(1)
+        }
+    }
+}
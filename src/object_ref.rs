@@ -0,0 +1,300 @@
+use winnow::{
+    ascii::{dec_int, dec_uint, line_ending},
+    combinator::{opt, repeat, repeat_till},
+    token::{rest, take_until},
+    Parser, Result,
+};
+
+use crate::parser::{
+    parse_assignment, parse_kind, parse_number, parse_sprite, separator,
+    types::{Number, Object, ObjectKind, Sprite},
+};
+
+/// Comma separated sprite indices as written in the file, parsed when
+/// read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IndexList<'a>(&'a str);
+
+impl<'a> IndexList<'a> {
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + 'a {
+        // Checked to be integers by `parse_object_ref`.
+        self.0.split(',').filter_map(|i| i.parse().ok())
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.iter().collect()
+    }
+}
+
+/// The sprite blocks of an object as written in the file, parsed when
+/// read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpriteList<'a> {
+    raw: &'a str,
+    len: usize,
+}
+
+impl<'a> SpriteList<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Sprite> + 'a {
+        let mut input = self.raw;
+        // Checked to be well formed by `parse_object_ref`.
+        std::iter::from_fn(move || {
+            let sprite = parse_sprite(&mut input).ok()?;
+            separator(&mut input).ok()?;
+            Some(sprite)
+        })
+        .take(self.len)
+    }
+}
+
+/// An object borrowing its text and index lists from the object file
+/// contents, for scanning many objects without allocating. The fields
+/// match `Object`'s, which `to_object` converts it into.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectRef<'a> {
+    pub id: u64,
+    pub description: &'a str,
+    pub kind: ObjectKind,
+    pub num_sprites: u64,
+    pub sprites: SpriteList<'a>,
+    pub sprites_drawn_behind: Option<IndexList<'a>>,
+    pub sprites_additive_blend: Option<IndexList<'a>>,
+    pub head_index: IndexList<'a>,
+    pub body_index: IndexList<'a>,
+    pub back_foot_index: IndexList<'a>,
+    pub front_foot_index: IndexList<'a>,
+    pub num_uses: u64,
+    pub use_chance: Option<Number>,
+    pub use_vanish_index: IndexList<'a>,
+    pub use_appear_index: IndexList<'a>,
+}
+
+impl ObjectRef<'_> {
+    pub fn to_object(&self) -> Object {
+        let list = |l: &IndexList| l.to_vec();
+
+        Object {
+            id: self.id,
+            description: self.description.to_string(),
+            kind: self.kind.clone(),
+            num_sprites: self.num_sprites,
+            sprites: self.sprites.iter().collect(),
+            sprites_drawn_behind: self
+                .sprites_drawn_behind
+                .as_ref()
+                .map(list),
+            sprites_additive_blend: self
+                .sprites_additive_blend
+                .as_ref()
+                .map(list),
+            head_index: list(&self.head_index),
+            body_index: list(&self.body_index),
+            back_foot_index: list(&self.back_foot_index),
+            front_foot_index: list(&self.front_foot_index),
+            num_uses: self.num_uses,
+            use_chance: self.use_chance.clone(),
+            use_vanish_index: list(&self.use_vanish_index),
+            use_appear_index: list(&self.use_appear_index),
+            bounding_box: None,
+        }
+    }
+}
+
+impl From<ObjectRef<'_>> for Object {
+    fn from(object: ObjectRef<'_>) -> Self {
+        object.to_object()
+    }
+}
+
+fn index_list<'a>(input: &mut &'a str) -> Result<IndexList<'a>> {
+    (
+        dec_int::<_, i64, _>,
+        repeat::<_, _, (), _, _>(0.., (',', dec_int::<_, i64, _>)),
+    )
+        .take()
+        .map(IndexList)
+        .parse_next(input)
+}
+
+fn index_assignment<'a>(
+    input: &mut &'a str,
+    key: &str,
+) -> Result<IndexList<'a>> {
+    parse_assignment(input, key, index_list)
+}
+
+/// Parses the same text as `parser::parse_object`, without copying the
+/// description or collecting the sprites and index lists.
+pub fn parse_object_ref<'a>(input: &mut &'a str) -> Result<ObjectRef<'a>> {
+    let id: u64 = parse_assignment(input, "id", dec_uint)?;
+    line_ending(input)?;
+    let line = take_until(0.., '\n').parse_next(input)?;
+    let description = line.strip_suffix('\r').unwrap_or(line);
+    '\n'.parse_next(input)?;
+
+    let kind = parse_kind(input)?;
+
+    take_until(0.., "numSprites").parse_next(input)?;
+    let num_sprites: u64 = parse_assignment(input, "numSprites", dec_uint)?;
+    separator(input)?;
+
+    let terminator = |i: &mut &'a str| {
+        let behind = opt(|i: &mut &'a str| {
+            let list = index_assignment(i, "spritesDrawnBehind")?;
+            separator(i)?;
+            Ok(list)
+        })
+        .parse_next(i)?;
+        let blend = opt(|i: &mut &'a str| {
+            let list = index_assignment(i, "spritesAdditiveBlend")?;
+            separator(i)?;
+            Ok(list)
+        })
+        .parse_next(i)?;
+        let head_index = index_assignment(i, "headIndex")?;
+
+        Ok((behind, blend, head_index))
+    };
+    let sprites_start = *input;
+    let mut sprites_end = *input;
+    let (len, (sprites_drawn_behind, sprites_additive_blend, head_index)) =
+        repeat_till(
+            0..,
+            |i: &mut &'a str| {
+                parse_sprite(i)?;
+                separator(i)?;
+                sprites_end = *i;
+                Ok(())
+            },
+            terminator,
+        )
+        .parse_next(input)?;
+    let sprites = SpriteList {
+        raw: &sprites_start[..sprites_start.len() - sprites_end.len()],
+        len,
+    };
+    separator(input)?;
+
+    let body_index = index_assignment(input, "bodyIndex")?;
+    separator(input)?;
+    let back_foot_index = index_assignment(input, "backFootIndex")?;
+    separator(input)?;
+    let front_foot_index = index_assignment(input, "frontFootIndex")?;
+
+    let uses = opt(|i: &mut &'a str| {
+        separator(i)?;
+        let num_uses: u64 = parse_assignment(i, "numUses", dec_uint)?;
+        let use_chance = opt((",", parse_number).map(|(_, chance)| chance))
+            .parse_next(i)?;
+        separator(i)?;
+        let vanish = index_assignment(i, "useVanishIndex")?;
+        separator(i)?;
+        let appear = index_assignment(i, "useAppearIndex")?;
+
+        Ok((num_uses, use_chance, vanish, appear))
+    })
+    .parse_next(input)?;
+    let (num_uses, use_chance, use_vanish_index, use_appear_index) =
+        uses.unwrap_or((1, None, IndexList(""), IndexList("")));
+    rest(input)?;
+
+    Ok(ObjectRef {
+        id,
+        description,
+        kind,
+        num_sprites,
+        sprites,
+        sprites_drawn_behind,
+        sprites_additive_blend,
+        head_index,
+        body_index,
+        back_foot_index,
+        front_foot_index,
+        num_uses,
+        use_chance,
+        use_vanish_index,
+        use_appear_index,
+    })
+}
+
+#[cfg(test)]
+mod object_ref_tests {
+    use crate::{
+        object_ref::parse_object_ref,
+        parser::{
+            parse_object,
+            types::{
+                ClothingObject, NonPersonObject, Number, Object, ObjectKind,
+                Position, Sprite,
+            },
+        },
+        writer::to_object_text,
+    };
+
+    #[test]
+    fn test_matches_parse_object() {
+        let object = Object {
+            id: 30,
+            description: "Bowl of Stew #full".to_string(),
+            kind: ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Hat(Position {
+                    x: Number(1.0),
+                    y: Number(-2.5),
+                }),
+            )),
+            num_sprites: 3,
+            sprites: [10, 20, 30]
+                .map(|id| Sprite {
+                    id,
+                    parent: -1,
+                    ..Sprite::default()
+                })
+                .to_vec(),
+            sprites_drawn_behind: Some(vec![0, 2]),
+            sprites_additive_blend: Some(vec![1]),
+            head_index: vec![-1],
+            body_index: vec![0],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            num_uses: 3,
+            use_chance: Some(Number(0.5)),
+            use_vanish_index: vec![1, 2],
+            use_appear_index: vec![-1],
+            bounding_box: None,
+        };
+
+        for text in [
+            to_object_text(&object),
+            to_object_text(&object).replace('\n', "\r\n"),
+            to_object_text(&Object {
+                id: 1,
+                head_index: vec![-1],
+                ..Object::default()
+            }),
+        ] {
+            let object_ref = parse_object_ref(&mut text.as_str()).unwrap();
+            let owned = parse_object(&mut text.as_str()).unwrap();
+            assert_eq!(object_ref.to_object(), owned);
+        }
+
+        let text = to_object_text(&object);
+        let object_ref = parse_object_ref(&mut text.as_str()).unwrap();
+        assert_eq!(object_ref.description, "Bowl of Stew #full");
+        assert_eq!(object_ref.sprites.len(), 3);
+        assert_eq!(object_ref.sprites.iter().nth(1).unwrap().id, 20);
+        assert_eq!(object_ref.use_vanish_index.as_str(), "1,2");
+        assert!(parse_object_ref(&mut "id=1\nStone\n").is_err());
+    }
+}
@@ -2,7 +2,7 @@ pub mod types {
     use serde::{Deserialize, Serialize};
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct Object {
@@ -17,23 +17,36 @@ pub mod types {
         pub body_index: Vec<i64>,
         pub back_foot_index: Vec<i64>,
         pub front_foot_index: Vec<i64>,
+        /// Objects without a `numUses` line have a single use, which is
+        /// also assumed for JSON written before uses were parsed.
+        #[serde(default = "default_num_uses")]
+        pub num_uses: u64,
+        #[serde(default)]
+        pub use_chance: Option<Number>,
+        #[serde(default)]
+        pub use_vanish_index: Vec<i64>,
+        #[serde(default)]
+        pub use_appear_index: Vec<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub bounding_box: Option<BoundingBox>,
     }
 
-    #[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+    fn default_num_uses() -> u64 {
+        1
+    }
+
+    #[derive(
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+    )]
     #[serde(rename_all = "camelCase")]
     pub enum SpritesBlockTerminator {
         SpritesDrawnBehind(Vec<i64>),
         SpritesAdditiveBlend((Vec<i64>, Option<Vec<i64>>)),
+        #[default]
         HeadIndex,
     }
 
-    impl Default for SpritesBlockTerminator {
-        fn default() -> Self {
-            Self::HeadIndex
-        }
-    }
-
-    #[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub enum ObjectKind {
         Person(PersonCharacteristic),
@@ -47,7 +60,7 @@ pub mod types {
         }
     }
 
-    #[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub enum ClothingObject {
         Shoe(Position),
@@ -64,7 +77,7 @@ pub mod types {
     }
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub enum NonPersonObject {
@@ -74,7 +87,7 @@ pub mod types {
     }
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub enum PersonCharacteristic {
@@ -84,7 +97,7 @@ pub mod types {
     }
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct Sprite {
@@ -103,7 +116,7 @@ pub mod types {
     }
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct Position {
@@ -112,13 +125,13 @@ pub mod types {
     }
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct Number(pub f64);
 
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct ColorRGB {
@@ -127,8 +140,21 @@ pub mod types {
         pub b: Number,
     }
 
+    /// Axis-aligned extent of an object in object space, where `y` grows
+    /// upwards like sprite positions do.
+    #[derive(
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+    )]
+    #[serde(rename_all = "camelCase")]
+    pub struct BoundingBox {
+        pub min_x: f64,
+        pub min_y: f64,
+        pub max_x: f64,
+        pub max_y: f64,
+    }
+
     #[derive(
-        Debug, PartialEq, PartialOrd, Default, Serialize, Deserialize,
+        Debug, Clone, PartialEq, PartialOrd, Default, Serialize, Deserialize,
     )]
     #[serde(rename_all = "camelCase")]
     pub struct AgeRange {
@@ -137,8 +163,14 @@ pub mod types {
     }
 }
 
-use std::{fs, path::PathBuf, vec};
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    vec,
+};
 
+use rayon::prelude::*;
 use winnow::{
     ascii::{alphanumeric1, dec_int, dec_uint, float, line_ending},
     combinator::{alt, opt, repeat_till, separated},
@@ -154,8 +186,169 @@ use types::{
     SpritesBlockTerminator,
 };
 
-pub fn parse(objects_dir: &PathBuf) -> anyhow::Result<Vec<Object>> {
-    let mut objects = vec![];
+/// Order of the objects returned by `parse_ordered`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectOrder {
+    #[default]
+    Id,
+    /// By description, then by id.
+    Description,
+    /// By object file name, compared as text so `10.txt` comes before
+    /// `2.txt`.
+    File,
+}
+
+/// Reads and parses the object files on every core, returning the
+/// objects sorted by id.
+pub fn parse(objects_dir: &Path) -> anyhow::Result<Vec<Object>> {
+    parse_ordered(objects_dir, ObjectOrder::Id)
+}
+
+/// Like `parse`, with the objects in the given order.
+pub fn parse_ordered(
+    objects_dir: &Path,
+    order: ObjectOrder,
+) -> anyhow::Result<Vec<Object>> {
+    let parsed: Vec<Option<Object>> =
+        ordered_object_files(objects_dir, order)?
+            .par_iter()
+            .map(|path| {
+                let content = fs::read_to_string(path)?;
+                Ok(parse_object(&mut content.as_str()).ok())
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+    let mut objects: Vec<Object> = parsed.into_iter().flatten().collect();
+    sort_objects(&mut objects, order);
+    Ok(objects)
+}
+
+/// Sorts objects by id or description. Objects are left as they are for
+/// `ObjectOrder::File`, as they don't know their file: that order comes
+/// from reading them in `ordered_object_files` order.
+pub fn sort_objects(objects: &mut [Object], order: ObjectOrder) {
+    match order {
+        ObjectOrder::Id => objects.sort_by_key(|o| o.id),
+        ObjectOrder::Description => objects.sort_by(|a, b| {
+            a.description.cmp(&b.description).then(a.id.cmp(&b.id))
+        }),
+        ObjectOrder::File => (),
+    }
+}
+
+/// Why an object file couldn't be read as an object.
+#[derive(Debug)]
+pub enum ParseError {
+    Io { path: PathBuf, error: io::Error },
+    Syntax { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => {
+                write!(f, "can't read {}: {}", path.display(), error)
+            }
+            Self::Syntax { path, message } => {
+                write!(f, "invalid object {}:\n{}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Syntax { .. } => None,
+        }
+    }
+}
+
+/// Reads and parses one object file at a time, in id order, so only
+/// the current object is held in memory.
+#[derive(Debug)]
+pub struct ObjectReader {
+    files: vec::IntoIter<PathBuf>,
+}
+
+impl ObjectReader {
+    pub fn new(objects_dir: &Path) -> anyhow::Result<Self> {
+        Ok(Self::from_files(object_files(objects_dir)?))
+    }
+
+    /// Reads the given object files in the order they are listed.
+    pub fn from_files(files: Vec<PathBuf>) -> Self {
+        Self {
+            files: files.into_iter(),
+        }
+    }
+}
+
+impl Iterator for ObjectReader {
+    type Item = std::result::Result<Object, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.files.next()?;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) => return Some(Err(ParseError::Io { path, error })),
+        };
+
+        Some(parse_object.parse(content.as_str()).map_err(|e| {
+            ParseError::Syntax {
+                path,
+                message: e.to_string(),
+            }
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.files.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod object_reader_tests {
+    use std::{env, fs, process};
+
+    use crate::{
+        parser::{types::Object, ObjectReader, ParseError},
+        writer::to_object_text,
+    };
+
+    #[test]
+    fn test() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_object_reader_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let object = Object {
+            id: 7,
+            description: "Stone".to_string(),
+            head_index: vec![-1],
+            ..Object::default()
+        };
+        fs::write(dir.join("7.txt"), to_object_text(&object)).unwrap();
+        fs::write(dir.join("8.txt"), "id=8").unwrap();
+
+        let mut reader = ObjectReader::new(&dir).unwrap();
+        assert_eq!(reader.size_hint(), (2, Some(2)));
+        assert_eq!(reader.next().unwrap().unwrap().description, "Stone");
+        assert!(matches!(
+            reader.next(),
+            Some(Err(ParseError::Syntax { path, .. })) if path.ends_with("8.txt")
+        ));
+        assert!(reader.next().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+/// Lists the `.txt` files in the objects directory that hold objects,
+/// sorted by the number they are named after, so in id order. Files not
+/// named after a number come last, sorted by path.
+pub fn object_files(objects_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
     for entry in fs::read_dir(objects_dir)? {
         let entry = entry?;
         let path = entry.path();
@@ -170,66 +363,161 @@ pub fn parse(objects_dir: &PathBuf) -> anyhow::Result<Vec<Object>> {
 
         if let Some(ext) = path.extension() {
             if ext == "txt" && is_object_file {
-                let content = fs::read_to_string(&path)?;
-
-                if let Ok(obj) = parse_object(&mut content.as_str()) {
-                    objects.push(obj);
-                }
+                files.push(path);
             }
         }
     }
-    Ok(objects)
+    files.sort_by_cached_key(|path| {
+        let number = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok());
+        (number.is_none(), number, path.clone())
+    });
+    Ok(files)
 }
 
-fn parse_object(input: &mut &str) -> Result<Object> {
-    let id: u64 = parse_assignment(input, "id", dec_uint)?;
-    line_ending(input)?;
+/// Whether every object file is named after the id on its `id=` line, so
+/// that reading them in `object_files` order yields objects in id order.
+/// Only the first line of each file is read. Files without an `id=` line
+/// don't count, as they don't parse anyway.
+pub fn file_names_match_ids(files: &[PathBuf]) -> anyhow::Result<bool> {
+    for path in files {
+        let mut first_line = String::new();
+        BufReader::new(fs::File::open(path)?).read_line(&mut first_line)?;
+        let Some(id) = first_line
+            .trim_end()
+            .strip_prefix("id=")
+            .and_then(|id| id.parse::<u64>().ok())
+        else {
+            continue;
+        };
 
-    let (description, _): (String, &str) =
-        repeat_till(0.., none_of::<_, _, ContextError>(['\n']), line_ending)
-            .parse_next(input)?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str());
+        if stem.and_then(|stem| stem.parse::<u64>().ok()) != Some(id) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
 
-    take_until(0.., "person").parse_next(input)?; // skip the rest after
+/// The object files in the order `parse_ordered` reads them: by file name
+/// compared as text for `ObjectOrder::File`, and as `object_files` lists
+/// them otherwise.
+pub fn ordered_object_files(
+    objects_dir: &Path,
+    order: ObjectOrder,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = object_files(objects_dir)?;
+    if order == ObjectOrder::File {
+        files.sort();
+    }
+    Ok(files)
+}
 
-    let person: u8 = parse_assignment(input, "person", dec_uint)?;
-    let is_person = person > 0;
+#[cfg(test)]
+mod parse_ordered_tests {
+    use std::{env, fs, process};
 
-    take_until(0.., "male").parse_next(input)?;
+    use crate::{
+        parser::{
+            file_names_match_ids, object_files, parse, parse_ordered,
+            types::Object, ObjectOrder,
+        },
+        writer::to_object_text,
+    };
 
-    let male: u8 = parse_assignment(input, "male", dec_uint)?;
-    let is_male = male > 0;
+    #[test]
+    fn test() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_parse_ordered_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (id, description) in [(2, "Stone"), (10, "Basket"), (3, "Rose")] {
+            let object = Object {
+                id,
+                description: description.to_string(),
+                head_index: vec![-1],
+                ..Object::default()
+            };
+            fs::write(
+                dir.join(format!("{}.txt", id)),
+                to_object_text(&object),
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("nextObjectNumber.txt"), "11").unwrap();
+
+        let ids = |order| -> Vec<u64> {
+            parse_ordered(&dir, order)
+                .unwrap()
+                .iter()
+                .map(|o| o.id)
+                .collect()
+        };
+        assert_eq!(ids(ObjectOrder::Id), [2, 3, 10]);
+        assert_eq!(ids(ObjectOrder::Description), [10, 3, 2]);
+        assert_eq!(ids(ObjectOrder::File), [10, 2, 3]);
+        assert_eq!(parse(&dir).unwrap().len(), 3);
+
+        let files = object_files(&dir).unwrap();
+        assert!(file_names_match_ids(&files).unwrap());
+        fs::write(dir.join("4.txt"), "garbage").unwrap();
+        assert!(file_names_match_ids(&object_files(&dir).unwrap()).unwrap());
+        fs::copy(dir.join("3.txt"), dir.join("5.txt")).unwrap();
+        assert!(!file_names_match_ids(&object_files(&dir).unwrap()).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
 
-    take_until(0.., "clothing").parse_next(input)?;
+#[cfg(test)]
+mod object_json_tests {
+    use crate::parser::types::Object;
 
-    let clothing = parse_assignment(input, "clothing", alphanumeric1)?;
-    let is_clothing = clothing != "n";
-    separator(input)?;
-    let clothing_offset =
-        parse_assignment(input, "clothingOffset", |i: &mut &str| {
-            let x = parse_number.parse_next(i)?;
-            ','.parse_next(i)?;
-            let y = parse_number.parse_next(i)?;
+    #[test]
+    fn test_without_uses() {
+        let object: Object = serde_json::from_str(
+            r#"{
+                "id": 7,
+                "description": "Stone",
+                "kind": {"nonPerson": "other"},
+                "numSprites": 0,
+                "sprites": [],
+                "spritesDrawnBehind": null,
+                "spritesAdditiveBlend": null,
+                "headIndex": [-1],
+                "bodyIndex": [-1],
+                "backFootIndex": [-1],
+                "frontFootIndex": [-1]
+            }"#,
+        )
+        .unwrap();
 
-            Ok(Position { x, y })
-        })?;
+        assert_eq!(
+            object,
+            Object {
+                id: 7,
+                description: "Stone".to_string(),
+                head_index: vec![-1],
+                body_index: vec![-1],
+                back_foot_index: vec![-1],
+                front_foot_index: vec![-1],
+                num_uses: 1,
+                ..Object::default()
+            }
+        );
+    }
+}
 
-    let kind = if is_person {
-        if is_male {
-            ObjectKind::Person(PersonCharacteristic::Masculine)
-        } else {
-            ObjectKind::Person(PersonCharacteristic::Feminine)
-        }
-    } else if is_clothing {
-        ObjectKind::NonPerson(NonPersonObject::Clothing(match clothing {
-            "s" => ClothingObject::Shoe(clothing_offset),
-            "t" => ClothingObject::Tunic(clothing_offset),
-            "h" => ClothingObject::Hat(clothing_offset),
-            "b" => ClothingObject::Bottom(clothing_offset),
-            _ => ClothingObject::default(),
-        }))
-    } else {
-        ObjectKind::NonPerson(NonPersonObject::Other)
-    };
+pub fn parse_object(input: &mut &str) -> Result<Object> {
+    let id: u64 = parse_assignment(input, "id", dec_uint)?;
+    line_ending(input)?;
+
+    let (description, _): (String, &str) =
+        repeat_till(0.., none_of::<_, _, ContextError>(['\n']), line_ending)
+            .parse_next(input)?;
+
+    let kind = parse_kind(input)?;
 
     take_until(0.., "numSprites").parse_next(input)?;
     let num_sprites: u64 = parse_assignment(input, "numSprites", dec_uint)?;
@@ -258,6 +546,11 @@ fn parse_object(input: &mut &str) -> Result<Object> {
     separator(input)?;
     let front_foot_index =
         parse_assignment(input, "frontFootIndex", parse_index_list)?;
+    let (num_uses, use_chance, use_vanish_index, use_appear_index) = opt(
+        parse_uses,
+    )
+    .parse_next(input)?
+    .unwrap_or((1, None, vec![], vec![]));
     rest(input)?; // skip the rest
 
     Ok(Object {
@@ -272,6 +565,11 @@ fn parse_object(input: &mut &str) -> Result<Object> {
         body_index,
         back_foot_index,
         front_foot_index,
+        num_uses,
+        use_chance,
+        use_vanish_index,
+        use_appear_index,
+        bounding_box: None,
     })
 }
 
@@ -285,8 +583,8 @@ mod parse_object_tests {
     use crate::parser::{
         parse_object,
         types::{
-            AgeRange, ColorRGB, NonPersonObject, Number, Object, ObjectKind,
-            Position, Sprite,
+            AgeRange, ClothingObject, ColorRGB, NonPersonObject, Number,
+            Object, ObjectKind, Position, Sprite,
         },
     };
 
@@ -415,17 +713,143 @@ pixHeight=0";
                     head_index: vec![-1],
                     body_index: vec![-1],
                     back_foot_index: vec![-1],
-                    front_foot_index: vec![-1]
+                    front_foot_index: vec![-1],
+                    num_uses: 1,
+                    use_chance: Some(Number(1.0)),
+                    use_vanish_index: vec![-1],
+                    use_appear_index: vec![-1],
+                    bounding_box: None,
                 }
             ))
         );
     }
+
+    const BACKPACK: &str = "id=198
+Backpack
+person=0,noSpawn=0
+male=0
+clothing=p
+clothingOffset=-2.000000,5.000000
+numSprites=2
+spriteID=1300
+pos=0.000000,0.000000
+rot=0.000000
+hFlip=0
+color=1.000000,1.000000,1.000000
+ageRange=-1.000000,-1.000000
+parent=-1
+invisHolding=0,invisWorn=0,behindSlots=0
+spriteID=1301
+pos=0.000000,10.000000
+rot=0.000000
+hFlip=0
+color=1.000000,1.000000,1.000000
+ageRange=-1.000000,-1.000000
+parent=-1
+invisHolding=0,invisWorn=0,behindSlots=0
+spritesDrawnBehind=1
+headIndex=-1
+bodyIndex=-1
+backFootIndex=-1
+frontFootIndex=-1
+pixHeight=0";
+
+    #[test]
+    fn test_backpack() {
+        let object = parse_object.parse(BACKPACK).unwrap();
+
+        assert_eq!(
+            object.kind,
+            ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Backpack(Position {
+                    x: Number(-2.0),
+                    y: Number(5.0)
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn test_drawn_behind_without_additive_blend() {
+        let object = parse_object.parse(BACKPACK).unwrap();
+
+        assert_eq!(object.sprites.len(), 2);
+        assert_eq!(object.sprites_drawn_behind, Some(vec![1]));
+        assert_eq!(object.sprites_additive_blend, None);
+        assert_eq!(object.head_index, vec![-1]);
+    }
+}
+
+/// Reads the `person`, `male`, `clothing` and `clothingOffset` keys,
+/// skipping whatever comes between them.
+pub(crate) fn parse_kind(input: &mut &str) -> Result<ObjectKind> {
+    take_until(0.., "person").parse_next(input)?; // skip the keys before
+
+    let person: u8 = parse_assignment(input, "person", dec_uint)?;
+    let is_person = person > 0;
+
+    take_until(0.., "male").parse_next(input)?;
+
+    let male: u8 = parse_assignment(input, "male", dec_uint)?;
+    let is_male = male > 0;
+
+    take_until(0.., "clothing").parse_next(input)?;
+
+    let clothing = parse_assignment(input, "clothing", alphanumeric1)?;
+    let is_clothing = clothing != "n";
+    separator(input)?;
+    let clothing_offset =
+        parse_assignment(input, "clothingOffset", |i: &mut &str| {
+            let x = parse_number.parse_next(i)?;
+            ','.parse_next(i)?;
+            let y = parse_number.parse_next(i)?;
+
+            Ok(Position { x, y })
+        })?;
+
+    let kind = if is_person {
+        if is_male {
+            ObjectKind::Person(PersonCharacteristic::Masculine)
+        } else {
+            ObjectKind::Person(PersonCharacteristic::Feminine)
+        }
+    } else if is_clothing {
+        ObjectKind::NonPerson(NonPersonObject::Clothing(match clothing {
+            "s" => ClothingObject::Shoe(clothing_offset),
+            "t" => ClothingObject::Tunic(clothing_offset),
+            "h" => ClothingObject::Hat(clothing_offset),
+            "b" => ClothingObject::Bottom(clothing_offset),
+            "p" => ClothingObject::Backpack(clothing_offset),
+            _ => ClothingObject::default(),
+        }))
+    } else {
+        ObjectKind::NonPerson(NonPersonObject::Other)
+    };
+
+    Ok(kind)
 }
 
-fn separator<'a>(input: &mut &'a str) -> Result<&'a str> {
+pub(crate) fn separator<'a>(input: &mut &'a str) -> Result<&'a str> {
     alt((line_ending, ",")).parse_next(input)
 }
 
+type UsesBlock = (u64, Option<Number>, Vec<i64>, Vec<i64>);
+
+fn parse_uses(input: &mut &str) -> Result<UsesBlock> {
+    separator(input)?;
+    let num_uses: u64 = parse_assignment(input, "numUses", dec_uint)?;
+    let use_chance = opt((",", parse_number).map(|(_, chance)| chance))
+        .parse_next(input)?;
+    separator(input)?;
+    let use_vanish_index =
+        parse_assignment(input, "useVanishIndex", parse_index_list)?;
+    separator(input)?;
+    let use_appear_index =
+        parse_assignment(input, "useAppearIndex", parse_index_list)?;
+
+    Ok((num_uses, use_chance, use_vanish_index, use_appear_index))
+}
+
 type SpriteBlock = (Vec<Sprite>, (SpritesBlockTerminator, Vec<i64>));
 
 fn parse_sprites<'a>(input: &mut &'a str) -> Result<SpriteBlock> {
@@ -463,7 +887,7 @@ fn parse_sprites<'a>(input: &mut &'a str) -> Result<SpriteBlock> {
                 Some(blend) => SpritesBlockTerminator::SpritesAdditiveBlend(
                     (blend, Some(behind)),
                 ),
-                None => SpritesBlockTerminator::HeadIndex,
+                None => SpritesBlockTerminator::SpritesDrawnBehind(behind),
             },
             None => match b {
                 Some(blend) => SpritesBlockTerminator::SpritesAdditiveBlend(
@@ -650,7 +1074,7 @@ headIndex=-1";
     }
 }
 
-fn parse_sprite<'a>(input: &mut &'a str) -> Result<Sprite> {
+pub(crate) fn parse_sprite<'a>(input: &mut &'a str) -> Result<Sprite> {
     let separator = |i: &mut &'a str| alt((line_ending, ",")).parse_next(i);
     let id: u64 = parse_assignment(input, "spriteID", dec_uint)?;
     separator(input)?;
@@ -787,11 +1211,11 @@ invisCont=0";
     }
 }
 
-fn parse_number(input: &mut &str) -> Result<Number> {
+pub(crate) fn parse_number(input: &mut &str) -> Result<Number> {
     Ok(Number(float(input)?))
 }
 
-fn parse_assignment<I, O, E, P>(
+pub(crate) fn parse_assignment<I, O, E, P>(
     input: &mut I,
     key: &str,
     mut p: P,
@@ -0,0 +1,315 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::{ObjectDocument, Value},
+    parser::types::{AgeRange, ColorRGB, Number, Object, Position},
+};
+
+/// Changes to make, keyed by object id.
+pub type Patch = BTreeMap<u64, ObjectPatch>;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ObjectPatch {
+    pub description: Option<String>,
+    /// Changes to make, keyed by index into the object's sprites.
+    #[serde(default)]
+    pub sprites: BTreeMap<usize, SpritePatch>,
+}
+
+/// Sprite fields to overwrite, leaving out the ones to keep.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SpritePatch {
+    pub id: Option<u64>,
+    pub position: Option<Position>,
+    pub rot: Option<Number>,
+    pub h_flip: Option<Number>,
+    pub color: Option<ColorRGB>,
+    pub age_range: Option<AgeRange>,
+    pub parent: Option<i64>,
+}
+
+impl ObjectPatch {
+    /// Checks the changes can be written to an object file and read back
+    /// the same.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(description) = &self.description {
+            if description.contains(['\n', '\r']) {
+                bail!("description can't contain a line break");
+            }
+        }
+        for (index, changes) in &self.sprites {
+            if let Some(h_flip) = &changes.h_flip {
+                if h_flip.0.fract() != 0.0 {
+                    bail!(
+                        "sprite {} hFlip must be a whole number, not {}",
+                        index,
+                        h_flip.0
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn apply_to_object(
+    object: &mut Object,
+    patch: &ObjectPatch,
+) -> anyhow::Result<()> {
+    patch.validate()?;
+    if let Some(description) = &patch.description {
+        object.description = description.clone();
+    }
+
+    for (index, changes) in &patch.sprites {
+        let Some(sprite) = object.sprites.get_mut(*index) else {
+            bail!("object {} has no sprite {}", object.id, index);
+        };
+
+        if let Some(id) = changes.id {
+            sprite.id = id;
+        }
+        if let Some(position) = &changes.position {
+            sprite.position = position.clone();
+        }
+        if let Some(rot) = &changes.rot {
+            sprite.rot = rot.clone();
+        }
+        if let Some(h_flip) = &changes.h_flip {
+            sprite.h_flip = h_flip.clone();
+        }
+        if let Some(color) = &changes.color {
+            sprite.color = color.clone();
+        }
+        if let Some(age_range) = &changes.age_range {
+            sprite.age_range = age_range.clone();
+        }
+        if let Some(parent) = changes.parent {
+            sprite.parent = parent;
+        }
+    }
+    Ok(())
+}
+
+/// Applies the patch to an object file's document, leaving every line it
+/// doesn't touch as it was.
+pub fn apply_to_document(
+    document: &mut ObjectDocument,
+    patch: &ObjectPatch,
+) -> anyhow::Result<()> {
+    patch.validate()?;
+    if let Some(description) = &patch.description {
+        document.set_description(description);
+    }
+
+    for (index, changes) in &patch.sprites {
+        let mut set = |key: &str, value: Value| {
+            document
+                .sprite_field_mut(*index, key)
+                .map(|field| field.set_value(value))
+                .ok_or_else(|| {
+                    anyhow!("sprite {} has no {} field", index, key)
+                })
+        };
+
+        if let Some(id) = changes.id {
+            set("spriteID", Value::Int(id as i64))?;
+        }
+        if let Some(p) = &changes.position {
+            set("pos", Value::Numbers(vec![p.x.clone(), p.y.clone()]))?;
+        }
+        if let Some(rot) = &changes.rot {
+            set("rot", Value::Numbers(vec![rot.clone()]))?;
+        }
+        if let Some(h_flip) = &changes.h_flip {
+            set("hFlip", Value::Int(h_flip.0 as i64))?;
+        }
+        if let Some(c) = &changes.color {
+            set(
+                "color",
+                Value::Numbers(vec![c.r.clone(), c.g.clone(), c.b.clone()]),
+            )?;
+        }
+        if let Some(a) = &changes.age_range {
+            set(
+                "ageRange",
+                Value::Numbers(vec![a.min.clone(), a.max.clone()]),
+            )?;
+        }
+        if let Some(parent) = changes.parent {
+            set("parent", Value::Int(parent))?;
+        }
+    }
+    Ok(())
+}
+
+/// Line diff of two versions of a file, listing each run of removed and
+/// added lines under the old line number it starts at.
+pub fn line_diff(name: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // `common[i][j]` is the length of the longest common subsequence of
+    // `old[i..]` and `new[j..]`.
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let start = i;
+        let (mut removed, mut added) = (vec![], vec![]);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                break;
+            }
+            if j == new.len()
+                || (i < old.len() && common[i + 1][j] >= common[i][j + 1])
+            {
+                removed.push(old[i]);
+                i += 1;
+            } else {
+                added.push(new[j]);
+                j += 1;
+            }
+        }
+
+        if diff.is_empty() {
+            let _ = writeln!(diff, "--- {}\n+++ {}", name, name);
+        }
+        let _ = writeln!(diff, "@@ line {} @@", start + 1);
+        for line in removed {
+            let _ = writeln!(diff, "-{}", line);
+        }
+        for line in added {
+            let _ = writeln!(diff, "+{}", line);
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod apply_patch_tests {
+    use crate::{
+        document::ObjectDocument,
+        parser::types::{ColorRGB, Number},
+        patch::{
+            apply_to_document, apply_to_object, line_diff, ObjectPatch, Patch,
+        },
+    };
+
+    const SOURCE: &str = "id=12
+Hat
+heatValue=0
+person=0,noSpawn=0
+male=0
+clothing=h
+clothingOffset=0.000000,-2.500000
+numSprites=1
+spriteID=553
+pos=3.000000,-34.000000
+rot=0.000000
+hFlip=0
+color=1.000000,1.000000,1.000000
+ageRange=-1.000000,-1.000000
+parent=-1
+invisHolding=0,invisWorn=0,behindSlots=0
+headIndex=-1
+bodyIndex=-1
+backFootIndex=-1
+frontFootIndex=-1
+pixHeight=0";
+
+    #[test]
+    fn test() {
+        let patch: Patch = serde_json::from_str(
+            r#"{"12": {
+                "description": "Red Hat",
+                "sprites": {"0": {"color": {"r": 1.0, "g": 0.0, "b": 0.0}}}
+            }}"#,
+        )
+        .unwrap();
+        let changes = &patch[&12];
+
+        let mut document = ObjectDocument::parse(SOURCE);
+        apply_to_document(&mut document, changes).unwrap();
+        let mut object = ObjectDocument::parse(SOURCE).to_object().unwrap();
+        apply_to_object(&mut object, changes).unwrap();
+
+        assert_eq!(document.to_object().unwrap(), object);
+        assert_eq!(
+            object.sprites[0].color,
+            ColorRGB {
+                r: Number(1.0),
+                g: Number(0.0),
+                b: Number(0.0)
+            }
+        );
+        assert_eq!(
+            line_diff("12.txt", SOURCE, &document.to_text()),
+            "--- 12.txt
++++ 12.txt
+@@ line 2 @@
+-Hat
++Red Hat
+@@ line 13 @@
+-color=1.000000,1.000000,1.000000
++color=1.000000,0.000000,0.000000
+"
+        );
+
+        let mut bad = changes.clone();
+        bad.sprites.insert(1, bad.sprites[&0].clone());
+        assert!(apply_to_object(&mut object, &bad).is_err());
+        assert!(apply_to_document(&mut document, &bad).is_err());
+
+        for (bad, field) in [
+            (r#"{"description": "Red\nHat"}"#, "description"),
+            (r#"{"sprites": {"0": {"hFlip": 0.5}}}"#, "hFlip"),
+        ] {
+            let bad: ObjectPatch = serde_json::from_str(bad).unwrap();
+            let error = apply_to_document(&mut document, &bad).unwrap_err();
+            assert!(error.to_string().contains(field), "{}", error);
+            assert!(apply_to_object(&mut object, &bad).is_err());
+        }
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("1.txt", "a\nb\nc", "a\nb\nc"), "");
+        assert_eq!(
+            line_diff("1.txt", "a\nb\nc\nd", "a\nx\ny\nc\nd\ne"),
+            "--- 1.txt
++++ 1.txt
+@@ line 2 @@
+-b
++x
++y
+@@ line 5 @@
++e
+"
+        );
+        assert_eq!(
+            line_diff("1.txt", "a\nb\nc", "a"),
+            "--- 1.txt\n+++ 1.txt\n@@ line 2 @@\n-b\n-c\n"
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt;
+
+use winnow::{
+    ascii::{float, multispace0},
+    combinator::{alt, delimited, not, preceded, repeat, terminated},
+    error::{ContextError, StrContext},
+    token::{any, none_of, take_while},
+    Parser, Result,
+};
+
+use crate::parser::types::{
+    ClothingObject, NonPersonObject, Object, ObjectKind, PersonCharacteristic,
+};
+
+/// Object properties a query can look at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Id,
+    Description,
+    /// Dotted kind path such as `clothing.hat` or `person.feminine`.
+    Kind,
+    NumSprites,
+    NumUses,
+    /// Ids of the object's sprites, matching when any of them does.
+    Sprite,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "id" => Some(Self::Id),
+            "description" => Some(Self::Description),
+            "kind" => Some(Self::Kind),
+            "numSprites" => Some(Self::NumSprites),
+            "numUses" => Some(Self::NumUses),
+            "sprite" => Some(Self::Sprite),
+            _ => None,
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Self::Description | Self::Kind)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Case-insensitive substring match.
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: Field,
+        op: Op,
+        value: Literal,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    Syntax(String),
+    /// A comparison that doesn't make sense for the field, such as
+    /// `numSprites ~ "a"`.
+    InvalidComparison(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(e) => write!(f, "invalid query:\n{}", e),
+            Self::InvalidComparison(e) => {
+                write!(f, "invalid comparison {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A parsed filter expression over objects, e.g.
+/// `kind == clothing.hat && numSprites > 5 && description ~ "Rose"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub expr: Expr,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> std::result::Result<Self, QueryError> {
+        let expr = terminated(parse_or, multispace0)
+            .parse(input)
+            .map_err(|e| QueryError::Syntax(e.to_string()))?;
+        validate(&expr)?;
+
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, object: &Object) -> bool {
+        evaluate(&self.expr, object)
+    }
+}
+
+fn ws<'a, O>(
+    p: impl Parser<&'a str, O, ContextError>,
+) -> impl Parser<&'a str, O, ContextError> {
+    preceded(multispace0, p)
+}
+
+fn parse_or(input: &mut &str) -> Result<Expr> {
+    let first = parse_and(input)?;
+    let rest: Vec<Expr> =
+        repeat(0.., preceded(ws("||"), parse_and)).parse_next(input)?;
+
+    Ok(rest
+        .into_iter()
+        .fold(first, |a, b| Expr::Or(Box::new(a), Box::new(b))))
+}
+
+fn parse_and(input: &mut &str) -> Result<Expr> {
+    let first = parse_unary(input)?;
+    let rest: Vec<Expr> =
+        repeat(0.., preceded(ws("&&"), parse_unary)).parse_next(input)?;
+
+    Ok(rest
+        .into_iter()
+        .fold(first, |a, b| Expr::And(Box::new(a), Box::new(b))))
+}
+
+fn parse_unary(input: &mut &str) -> Result<Expr> {
+    alt((
+        preceded(ws('!'), parse_unary).map(|e| Expr::Not(Box::new(e))),
+        delimited(ws('('), parse_or, ws(')')),
+        parse_comparison,
+    ))
+    .parse_next(input)
+}
+
+/// Name of a field, or a bare word such as `clothing.hat`.
+fn parse_word<'a>(input: &mut &'a str) -> Result<&'a str> {
+    take_while(1.., |c: char| {
+        c.is_ascii_alphanumeric() || c == '_' || c == '.'
+    })
+    .parse_next(input)
+}
+
+fn parse_comparison(input: &mut &str) -> Result<Expr> {
+    let field = ws(parse_word.verify_map(Field::from_name))
+        .context(StrContext::Label("field"))
+        .parse_next(input)?;
+    let op = ws(alt((
+        "==".value(Op::Eq),
+        "!=".value(Op::Ne),
+        "<=".value(Op::Le),
+        ">=".value(Op::Ge),
+        "<".value(Op::Lt),
+        ">".value(Op::Gt),
+        "~".value(Op::Contains),
+    )))
+    .parse_next(input)?;
+    let value = ws(parse_literal).parse_next(input)?;
+
+    Ok(Expr::Compare { field, op, value })
+}
+
+fn parse_literal(input: &mut &str) -> Result<Literal> {
+    alt((
+        delimited(
+            '"',
+            repeat(0.., alt((none_of(['"', '\\']), preceded('\\', any)))),
+            '"',
+        )
+        .map(Literal::Text),
+        // Keeps words starting like a float, such as `info`, whole.
+        terminated(float, not(parse_word)).map(Literal::Number),
+        parse_word.map(|w: &str| Literal::Text(w.to_string())),
+    ))
+    .parse_next(input)
+}
+
+/// Rejects comparisons between a field and a value of the wrong type.
+fn validate(expr: &Expr) -> std::result::Result<(), QueryError> {
+    match expr {
+        Expr::And(a, b) | Expr::Or(a, b) => validate(a).and(validate(b)),
+        Expr::Not(e) => validate(e),
+        Expr::Compare { field, op, value } => {
+            let valid = match value {
+                Literal::Number(_) => !field.is_text() && *op != Op::Contains,
+                Literal::Text(_) => {
+                    field.is_text()
+                        && matches!(op, Op::Eq | Op::Ne | Op::Contains)
+                }
+            };
+
+            if valid {
+                Ok(())
+            } else {
+                Err(QueryError::InvalidComparison(format!(
+                    "{:?} {:?} {:?}",
+                    field, op, value
+                )))
+            }
+        }
+    }
+}
+
+/// Dotted path of the object's kind, e.g. `clothing.hat`.
+pub fn kind_path(kind: &ObjectKind) -> &'static str {
+    match kind {
+        ObjectKind::Person(PersonCharacteristic::Feminine) => {
+            "person.feminine"
+        }
+        ObjectKind::Person(PersonCharacteristic::Masculine) => {
+            "person.masculine"
+        }
+        ObjectKind::NonPerson(NonPersonObject::Other) => "other",
+        ObjectKind::NonPerson(NonPersonObject::Clothing(c)) => match c {
+            ClothingObject::Shoe(_) => "clothing.shoe",
+            ClothingObject::Tunic(_) => "clothing.tunic",
+            ClothingObject::Hat(_) => "clothing.hat",
+            ClothingObject::Bottom(_) => "clothing.bottom",
+            ClothingObject::Backpack(_) => "clothing.backpack",
+        },
+    }
+}
+
+fn compare_number(a: f64, op: Op, b: f64) -> bool {
+    match op {
+        Op::Eq => a == b,
+        Op::Ne => a != b,
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        Op::Contains => false,
+    }
+}
+
+fn evaluate(expr: &Expr, object: &Object) -> bool {
+    let (field, op, value) = match expr {
+        Expr::And(a, b) => return evaluate(a, object) && evaluate(b, object),
+        Expr::Or(a, b) => return evaluate(a, object) || evaluate(b, object),
+        Expr::Not(e) => return !evaluate(e, object),
+        Expr::Compare { field, op, value } => (field, *op, value),
+    };
+
+    match (field, value) {
+        (Field::Description, Literal::Text(text)) => match op {
+            Op::Eq => object.description == *text,
+            Op::Ne => object.description != *text,
+            _ => object
+                .description
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+        },
+        (Field::Kind, Literal::Text(text)) => {
+            let path = kind_path(&object.kind);
+            // `kind == clothing` also matches every kind of clothing.
+            let is_kind = path == text
+                || path
+                    .strip_prefix(text.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'));
+            match op {
+                Op::Eq => is_kind,
+                Op::Ne => !is_kind,
+                _ => path.contains(&text.to_lowercase()),
+            }
+        }
+        (Field::Sprite, Literal::Number(n)) => match op {
+            Op::Ne => object.sprites.iter().all(|s| s.id as f64 != *n),
+            _ => object
+                .sprites
+                .iter()
+                .any(|s| compare_number(s.id as f64, op, *n)),
+        },
+        (Field::Id, Literal::Number(n)) => {
+            compare_number(object.id as f64, op, *n)
+        }
+        (Field::NumSprites, Literal::Number(n)) => {
+            compare_number(object.num_sprites as f64, op, *n)
+        }
+        (Field::NumUses, Literal::Number(n)) => {
+            compare_number(object.num_uses as f64, op, *n)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use crate::{
+        parser::types::{
+            ClothingObject, NonPersonObject, Object, ObjectKind, Position,
+            Sprite,
+        },
+        query::{Query, QueryError},
+    };
+
+    fn hat() -> Object {
+        Object {
+            id: 7767,
+            description: "Rose Crown with Rose".to_string(),
+            kind: ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Hat(Position::default()),
+            )),
+            num_sprites: 6,
+            sprites: [111068, 1304]
+                .map(|id| Sprite {
+                    id,
+                    ..Sprite::default()
+                })
+                .to_vec(),
+            num_uses: 1,
+            ..Object::default()
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::parse(query).unwrap().matches(&hat())
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(
+            r#"kind == clothing.hat && numSprites > 5 && description ~ "rose""#
+        ));
+        assert!(matches("kind == clothing"));
+        assert!(!matches("kind == cloth"));
+        assert!(matches("kind != person || id == 1"));
+        assert!(matches("!(id < 100) && (sprite == 1304 || sprite == 5)"));
+        assert!(!matches("sprite != 1304"));
+        assert!(matches(r#"description == "Rose Crown with Rose""#));
+        assert!(!matches(r#"description ~ "\"Rose""#));
+        assert!(matches("numUses <= 1 && numSprites >= 6"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Query::parse("size > 3"),
+            Err(QueryError::Syntax(_))
+        ));
+        assert!(matches!(
+            Query::parse("id == 3 &&"),
+            Err(QueryError::Syntax(_))
+        ));
+        assert!(matches!(
+            Query::parse("numSprites ~ rose"),
+            Err(QueryError::InvalidComparison(_))
+        ));
+        assert!(matches!(
+            Query::parse("description > 3"),
+            Err(QueryError::InvalidComparison(_))
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    document::{ObjectDocument, Value},
+    parser::types::Object,
+};
+
+/// New sprite id for each old one.
+pub type SpriteMapping = BTreeMap<u64, u64>;
+
+/// Replaces sprite ids across the objects, returning the ids of the objects
+/// that changed.
+pub fn remap_sprites(
+    objects: &mut [Object],
+    mapping: &SpriteMapping,
+) -> Vec<u64> {
+    let mut changed = vec![];
+    for object in objects {
+        let mut is_changed = false;
+        for sprite in &mut object.sprites {
+            if let Some(new_id) = mapping.get(&sprite.id) {
+                is_changed |= sprite.id != *new_id;
+                sprite.id = *new_id;
+            }
+        }
+
+        if is_changed {
+            changed.push(object.id);
+        }
+    }
+    changed
+}
+
+/// Replaces the `spriteID` values of an object file's document, returning
+/// whether any changed.
+pub fn remap_document_sprites(
+    document: &mut ObjectDocument,
+    mapping: &SpriteMapping,
+) -> bool {
+    let mut changed = false;
+    for field in document.fields_mut().filter(|f| f.key == "spriteID") {
+        let new_id = match field.value() {
+            Some(Value::Int(id)) => u64::try_from(*id)
+                .ok()
+                .and_then(|id| mapping.get(&id))
+                .filter(|new_id| **new_id as i64 != *id),
+            _ => None,
+        };
+
+        if let Some(new_id) = new_id {
+            field.set_value(Value::Int(*new_id as i64));
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Sprite ids the mapping points to that aren't among `existing`.
+pub fn missing_targets(
+    mapping: &SpriteMapping,
+    existing: &BTreeSet<u64>,
+) -> Vec<u64> {
+    let targets: BTreeSet<u64> = mapping.values().copied().collect();
+
+    targets.difference(existing).copied().collect()
+}
+
+#[cfg(test)]
+mod remap_sprites_tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::{
+        document::ObjectDocument,
+        parser::types::{Object, Sprite},
+        remap::{missing_targets, remap_document_sprites, remap_sprites},
+    };
+
+    #[test]
+    fn test() {
+        let mapping = BTreeMap::from([(1, 10), (2, 2)]);
+        let object = |id: u64, sprite_ids: &[u64]| Object {
+            id,
+            sprites: sprite_ids
+                .iter()
+                .map(|id| Sprite {
+                    id: *id,
+                    ..Sprite::default()
+                })
+                .collect(),
+            ..Object::default()
+        };
+        let mut objects =
+            vec![object(100, &[1, 3]), object(101, &[2]), object(102, &[])];
+
+        assert_eq!(remap_sprites(&mut objects, &mapping), vec![100]);
+        assert_eq!(objects[0], object(100, &[10, 3]));
+        assert_eq!(
+            missing_targets(&mapping, &BTreeSet::from([2, 3])),
+            vec![10]
+        );
+
+        let mut document =
+            ObjectDocument::parse("id=100\nStick\nspriteID=1\nspriteID=3");
+        assert!(remap_document_sprites(&mut document, &mapping));
+        assert_eq!(
+            document.to_text(),
+            "id=100\nStick\nspriteID=10\nspriteID=3"
+        );
+        assert!(!remap_document_sprites(&mut document, &mapping));
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    geometry::bounding_box, parser::types::Object, sprites::SpriteImages,
+    transform::resolve_transforms,
+};
+
+/// Draws the object's sprites in list order at one pixel per unit.
+///
+/// Sprites are placed by `resolve_transforms`, so at their own `pos` like
+/// the game's `drawObject` does.
+///
+/// Sprites are tinted by their `color` and the ones listed in
+/// `sprites_additive_blend` are added onto what is below them. Returns
+/// `None` when none of the sprite images can be found.
+pub fn render_object(
+    object: &Object,
+    images: &mut SpriteImages,
+) -> anyhow::Result<Option<RgbaImage>> {
+    render_object_sprites(object, images, |_| true)
+}
+
+/// Like [`render_object`], but only draws the sprites whose index passes
+/// `visible`. The canvas still covers every sprite, so renders of the same
+/// object line up with each other.
+pub fn render_object_sprites(
+    object: &Object,
+    images: &mut SpriteImages,
+    visible: impl Fn(usize) -> bool,
+) -> anyhow::Result<Option<RgbaImage>> {
+    let transforms = resolve_transforms(object)?;
+    let sizes: HashMap<_, _> = object
+        .sprites
+        .iter()
+        .filter_map(|s| images.size(s.id).map(|size| (s.id, size)))
+        .collect();
+    let Some(bounds) = bounding_box(object, &sizes) else {
+        return Ok(None);
+    };
+
+    let (min_x, max_y) = (bounds.min_x.floor(), bounds.max_y.ceil());
+    let width = (bounds.max_x.ceil() - min_x).max(1.0) as u32;
+    let height = (max_y - bounds.min_y.floor()).max(1.0) as u32;
+    let mut canvas = RgbaImage::new(width, height);
+    let additive = object.sprites_additive_blend.as_deref().unwrap_or(&[]);
+
+    for (i, (sprite, world)) in
+        object.sprites.iter().zip(&transforms).enumerate()
+    {
+        if !visible(i) {
+            continue;
+        }
+        let matrix = world.matrix;
+        let Some(inverse) = matrix.inverse() else {
+            continue;
+        };
+        let Some(image) = images.get(sprite.id) else {
+            continue;
+        };
+        let is_additive = additive.contains(&(i as i64));
+        let tint = [sprite.color.r.0, sprite.color.g.0, sprite.color.b.0];
+        let (hw, hh) =
+            (image.width() as f64 / 2.0, image.height() as f64 / 2.0);
+        let corners = [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+            .map(|(u, v)| matrix.apply(u, v));
+        let xs = corners.map(|(x, _)| x);
+        let ys = corners.map(|(_, y)| y);
+
+        // Only visit the canvas pixels the sprite can cover.
+        let px_from = (min_of(xs) - min_x).floor().max(0.0) as u32;
+        let px_to = ((max_of(xs) - min_x).ceil() as u32).min(width);
+        let py_from = (max_y - max_of(ys)).floor().max(0.0) as u32;
+        let py_to = ((max_y - min_of(ys)).ceil() as u32).min(height);
+
+        for py in py_from..py_to {
+            for px in px_from..px_to {
+                let x = min_x + px as f64 + 0.5;
+                let y = max_y - py as f64 - 0.5;
+                let (u, v) = inverse.apply(x, y);
+                let (ix, iy) = ((u + hw).floor(), (hh - v).floor());
+                if ix < 0.0
+                    || iy < 0.0
+                    || ix >= image.width() as f64
+                    || iy >= image.height() as f64
+                {
+                    continue;
+                }
+
+                let src = image.get_pixel(ix as u32, iy as u32);
+                let dst = canvas.get_pixel_mut(px, py);
+                blend(dst, src, tint, is_additive);
+            }
+        }
+    }
+    Ok(Some(canvas))
+}
+
+fn min_of(values: [f64; 4]) -> f64 {
+    values.into_iter().fold(f64::MAX, f64::min)
+}
+
+fn max_of(values: [f64; 4]) -> f64 {
+    values.into_iter().fold(f64::MIN, f64::max)
+}
+
+/// Composites a tinted source pixel over `dst`, both with straight alpha.
+fn blend(dst: &mut Rgba<u8>, src: &Rgba<u8>, tint: [f64; 3], additive: bool) {
+    let src_a = src[3] as f64 / 255.0;
+    if src_a == 0.0 {
+        return;
+    }
+    let dst_a = dst[3] as f64 / 255.0;
+    let out_a = if additive {
+        (src_a + dst_a).min(1.0)
+    } else {
+        src_a + dst_a * (1.0 - src_a)
+    };
+
+    for c in 0..3 {
+        let s = src[c] as f64 * tint[c].clamp(0.0, 1.0) * src_a;
+        let d = dst[c] as f64 * dst_a;
+        let out = if additive {
+            s + d
+        } else {
+            s + d * (1.0 - src_a)
+        };
+        dst[c] = (out / out_a).round().clamp(0.0, 255.0) as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod render_object_tests {
+    use std::{env, fs, process};
+
+    use image::{Rgba, RgbaImage};
+
+    use crate::{
+        parser::types::{ColorRGB, Number, Object, Position, Sprite},
+        render::render_object,
+        sprites::SpriteImages,
+    };
+
+    #[test]
+    fn test() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_render_object_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(4, 2, Rgba([200, 100, 50, 255]))
+            .save(dir.join("1.tga"))
+            .unwrap();
+
+        let sprite = |x: f64, parent: i64| Sprite {
+            id: 1,
+            position: Position {
+                x: Number(x),
+                y: Number(0.0),
+            },
+            color: ColorRGB {
+                r: Number(1.0),
+                g: Number(0.5),
+                b: Number(0.0),
+            },
+            parent,
+            ..Sprite::default()
+        };
+        // The parent doesn't move the second sprite, its `pos` is already
+        // in object space.
+        let object = Object {
+            sprites: vec![sprite(2.0, -1), sprite(8.0, 0), Sprite::default()],
+            ..Object::default()
+        };
+        let rendered = render_object(&object, &mut SpriteImages::new(&dir))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!((rendered.width(), rendered.height()), (10, 2));
+        assert_eq!(*rendered.get_pixel(0, 0), Rgba([200, 50, 0, 255]));
+        assert_eq!(*rendered.get_pixel(4, 1), Rgba([0, 0, 0, 0]));
+        assert_eq!(*rendered.get_pixel(9, 1), Rgba([200, 50, 0, 255]));
+        assert_eq!(
+            render_object(&Object::default(), &mut SpriteImages::new(&dir))
+                .unwrap(),
+            None
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{description::Description, parser::types::Object};
+
+/// Lowercased runs of letters and digits.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Inverted index over the names and tags of object descriptions, leaving
+/// out designer comments.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    /// token -> object id -> times the token appears in its name and tags
+    postings: BTreeMap<String, BTreeMap<u64, u32>>,
+    descriptions: BTreeMap<u64, Description>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub id: u64,
+    pub name: String,
+    pub description: String,
+    pub score: f64,
+}
+
+/// How much more a whole word counts than a word merely starting with the
+/// term.
+const EXACT_WEIGHT: f64 = 2.0;
+
+impl SearchIndex {
+    pub fn build(objects: &[Object]) -> Self {
+        let mut index = Self::default();
+        for object in objects {
+            let description = Description::parse(&object.description);
+            let tokens = tokenize(&description.name)
+                .into_iter()
+                .chain(description.tags.iter().flat_map(|t| tokenize(t)));
+            for token in tokens {
+                *index
+                    .postings
+                    .entry(token)
+                    .or_default()
+                    .entry(object.id)
+                    .or_default() += 1;
+            }
+            index.descriptions.insert(object.id, description);
+        }
+        index
+    }
+
+    /// Objects whose name or tags have a word starting with every term,
+    /// best first. Rarer words and whole word matches score higher, ties
+    /// go to the lower id.
+    pub fn search(&self, terms: &str) -> Vec<SearchHit> {
+        let terms = tokenize(terms);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let total = self.descriptions.len() as f64;
+        let mut scores: HashMap<u64, f64> = HashMap::new();
+
+        for (i, term) in terms.iter().enumerate() {
+            // Best score of the term in each object.
+            let mut term_scores: HashMap<u64, f64> = HashMap::new();
+            let matching = self
+                .postings
+                .range(term.clone()..)
+                .take_while(|(token, _)| token.starts_with(term.as_str()));
+            for (token, objects) in matching {
+                let weight = if token == term { EXACT_WEIGHT } else { 1.0 };
+                let idf = (1.0 + total / objects.len() as f64).ln();
+                for (&id, &count) in objects {
+                    let score = weight * idf * count as f64;
+                    let best = term_scores.entry(id).or_default();
+                    *best = best.max(score);
+                }
+            }
+
+            if i == 0 {
+                scores = term_scores;
+            } else {
+                scores.retain(|id, score| match term_scores.get(id) {
+                    Some(term_score) => {
+                        *score += term_score;
+                        true
+                    }
+                    None => false,
+                });
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(id, score)| {
+                let description = &self.descriptions[&id];
+                SearchHit {
+                    id,
+                    name: description.name.clone(),
+                    description: description.raw.clone(),
+                    score,
+                }
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id))
+        });
+        hits
+    }
+}
+
+#[cfg(test)]
+mod search_index_tests {
+    use crate::{
+        parser::types::Object,
+        search::{tokenize, SearchIndex},
+    };
+
+    fn object(id: u64, description: &str) -> Object {
+        Object {
+            id,
+            description: description.to_string(),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(
+            tokenize("Rose Crown, with-ROSE"),
+            ["rose", "crown", "with", "rose"]
+        );
+
+        let index = SearchIndex::build(&[
+            object(1, "Rose Crown with Rose"),
+            object(2, "Rosemary"),
+            object(3, "Wooden Crown"),
+            object(4, "Basket"),
+            object(5, "Bowl of Stew +food #full crown"),
+        ]);
+        let ids = |terms| -> Vec<u64> {
+            index.search(terms).iter().map(|h| h.id).collect()
+        };
+
+        assert_eq!(ids("ROSE"), [1, 2]);
+        assert_eq!(ids("ros"), [1, 2]);
+        assert_eq!(ids("crown"), [1, 3]);
+        assert_eq!(ids("cro wood"), [3]);
+        assert_eq!(ids("crown basket"), Vec::<u64>::new());
+        assert_eq!(ids("  "), Vec::<u64>::new());
+        assert_eq!(index.search("basket")[0].description, "Basket");
+        // Tags count, designer comments don't.
+        assert_eq!(ids("food"), [5]);
+        assert_eq!(ids("full"), Vec::<u64>::new());
+        let stew = &index.search("stew")[0];
+        assert_eq!(stew.name, "Bowl of Stew");
+        assert_eq!(stew.description, "Bowl of Stew +food #full crown");
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+
+use crate::parser::types::{Object, Sprite};
+
+/// Why an old and a new sprite were paired up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Pairing {
+    /// Same id, and in the same order relative to the other kept sprites,
+    /// even if its index shifted.
+    Kept,
+    /// Same id, but moved past other sprites.
+    Reordered,
+    /// Different id in the same place, rotation and flip.
+    Replaced,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpritePair {
+    pub old: usize,
+    pub new: usize,
+    pub pairing: Pairing,
+}
+
+/// How the sprites of two versions of an object correspond.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteAlignment {
+    /// In new index order.
+    pub pairs: Vec<SpritePair>,
+    /// New indices without an old sprite.
+    pub added: Vec<usize>,
+    /// Old indices without a new sprite.
+    pub removed: Vec<usize>,
+}
+
+fn same_geometry(a: &Sprite, b: &Sprite) -> bool {
+    a.position == b.position && a.rot == b.rot && a.h_flip == b.h_flip
+}
+
+/// Aligns the sprite lists in three passes: the longest common
+/// subsequence of ids, preferring sprites whose geometry is unchanged,
+/// then the remaining sprites with equal ids, nearest first, then the
+/// remaining sprites with equal geometry. Inserting a sprite therefore
+/// only shifts the indices of the sprites after it.
+pub fn align_sprites(old: &[Sprite], new: &[Sprite]) -> SpriteAlignment {
+    let (n, m) = (old.len(), new.len());
+    let mut old_partner: Vec<Option<(usize, Pairing)>> = vec![None; n];
+
+    // best[j][i] is the (matches, unchanged geometry) of the best common
+    // subsequence of old[j..] and new[i..].
+    let mut best = vec![vec![(0u32, 0u32); m + 1]; n + 1];
+    for j in (0..n).rev() {
+        for i in (0..m).rev() {
+            best[j][i] = best[j + 1][i].max(best[j][i + 1]);
+            if old[j].id == new[i].id {
+                let (matches, geometry) = best[j + 1][i + 1];
+                let unchanged = u32::from(same_geometry(&old[j], &new[i]));
+                best[j][i] =
+                    best[j][i].max((matches + 1, geometry + unchanged));
+            }
+        }
+    }
+    let (mut j, mut i) = (0, 0);
+    while j < n && i < m {
+        let unchanged = u32::from(same_geometry(&old[j], &new[i]));
+        let (matches, geometry) = best[j + 1][i + 1];
+        if old[j].id == new[i].id
+            && best[j][i] == (matches + 1, geometry + unchanged)
+        {
+            old_partner[j] = Some((i, Pairing::Kept));
+            j += 1;
+            i += 1;
+        } else if best[j + 1][i] >= best[j][i + 1] {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut new_paired = vec![false; m];
+    for (i, _) in old_partner.iter().flatten() {
+        new_paired[*i] = true;
+    }
+
+    let distance = |a: &Sprite, b: &Sprite| {
+        (a.position.x.0 - b.position.x.0)
+            .hypot(a.position.y.0 - b.position.y.0)
+    };
+    for i in 0..m {
+        if new_paired[i] {
+            continue;
+        }
+        let nearest = (0..n)
+            .filter(|&j| old_partner[j].is_none() && old[j].id == new[i].id)
+            .min_by(|&a, &b| {
+                distance(&old[a], &new[i])
+                    .total_cmp(&distance(&old[b], &new[i]))
+            });
+        if let Some(j) = nearest {
+            old_partner[j] = Some((i, Pairing::Reordered));
+            new_paired[i] = true;
+        }
+    }
+    for i in 0..m {
+        if new_paired[i] {
+            continue;
+        }
+        let same_place = (0..n).find(|&j| {
+            old_partner[j].is_none() && same_geometry(&old[j], &new[i])
+        });
+        if let Some(j) = same_place {
+            old_partner[j] = Some((i, Pairing::Replaced));
+            new_paired[i] = true;
+        }
+    }
+
+    let mut pairs: Vec<SpritePair> = old_partner
+        .iter()
+        .enumerate()
+        .filter_map(|(j, p)| {
+            p.map(|(i, pairing)| SpritePair {
+                old: j,
+                new: i,
+                pairing,
+            })
+        })
+        .collect();
+    pairs.sort_by_key(|p| p.new);
+
+    SpriteAlignment {
+        pairs,
+        added: (0..m).filter(|&i| !new_paired[i]).collect(),
+        removed: (0..n).filter(|&j| old_partner[j].is_none()).collect(),
+    }
+}
+
+/// An old sprite index and the index the same sprite has now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexMove {
+    pub old: usize,
+    pub new: usize,
+}
+
+impl SpriteAlignment {
+    /// New index of the sprite at an old index, if it is still there.
+    pub fn new_index(&self, old: usize) -> Option<usize> {
+        self.pairs.iter().find(|p| p.old == old).map(|p| p.new)
+    }
+
+    /// Every paired sprite whose index changed, in old index order.
+    pub fn moves(&self) -> Vec<IndexMove> {
+        let mut moves: Vec<IndexMove> = self
+            .pairs
+            .iter()
+            .filter(|p| p.old != p.new)
+            .map(|p| IndexMove {
+                old: p.old,
+                new: p.new,
+            })
+            .collect();
+        moves.sort_by_key(|m| m.old);
+        moves
+    }
+
+    /// Sorted new indices of the sprites an old index list points at,
+    /// leaving out `-1` and sprites that were removed.
+    pub fn remap_indices(&self, indices: &[i64]) -> Vec<i64> {
+        let mut remapped: Vec<i64> = indices
+            .iter()
+            .filter_map(|&i| usize::try_from(i).ok())
+            .filter_map(|i| self.new_index(i))
+            .map(|i| i as i64)
+            .collect();
+        remapped.sort_unstable();
+        remapped
+    }
+}
+
+/// An index list field whose sprites changed, beyond their indices moving
+/// along with the sprite list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexFieldChange {
+    /// Key as written in object files, e.g. `headIndex`.
+    pub field: String,
+    pub old: Vec<i64>,
+    pub new: Vec<i64>,
+    /// The old list with the sprites' new indices, what `new` would be
+    /// had the field not been edited.
+    pub remapped: Vec<i64>,
+}
+
+/// Index list fields by their object file key.
+pub fn index_fields(object: &Object) -> [(&'static str, &[i64]); 8] {
+    [
+        (
+            "spritesDrawnBehind",
+            object.sprites_drawn_behind.as_deref().unwrap_or(&[]),
+        ),
+        (
+            "spritesAdditiveBlend",
+            object.sprites_additive_blend.as_deref().unwrap_or(&[]),
+        ),
+        ("headIndex", &object.head_index),
+        ("bodyIndex", &object.body_index),
+        ("backFootIndex", &object.back_foot_index),
+        ("frontFootIndex", &object.front_foot_index),
+        ("useVanishIndex", &object.use_vanish_index),
+        ("useAppearIndex", &object.use_appear_index),
+    ]
+}
+
+/// Compares every index list field after remapping the old one through
+/// the alignment, so fields that only follow shifted sprites aren't
+/// reported.
+pub fn diff_index_fields(
+    old: &Object,
+    new: &Object,
+    alignment: &SpriteAlignment,
+) -> Vec<IndexFieldChange> {
+    index_fields(old)
+        .into_iter()
+        .zip(index_fields(new))
+        .filter_map(|((field, old_indices), (_, new_indices))| {
+            let remapped = alignment.remap_indices(old_indices);
+            let mut current: Vec<i64> =
+                new_indices.iter().copied().filter(|&i| i >= 0).collect();
+            current.sort_unstable();
+
+            (current != remapped).then(|| IndexFieldChange {
+                field: field.to_string(),
+                old: old_indices.to_vec(),
+                new: new_indices.to_vec(),
+                remapped,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod sprite_alignment_tests {
+    use crate::{
+        parser::types::{Number, Object, Position, Sprite},
+        sprite_diff::{
+            align_sprites, diff_index_fields, IndexMove, Pairing, SpritePair,
+        },
+    };
+
+    fn sprite(id: u64, x: f64) -> Sprite {
+        Sprite {
+            id,
+            position: Position {
+                x: Number(x),
+                y: Number(0.0),
+            },
+            ..Sprite::default()
+        }
+    }
+
+    fn object(sprites: Vec<Sprite>, head_index: Vec<i64>) -> Object {
+        Object {
+            sprites,
+            head_index,
+            body_index: vec![-1],
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn test_insertion_shifts_indices() {
+        let old = object(vec![sprite(10, 0.0), sprite(20, 1.0)], vec![1]);
+        let new = object(
+            vec![sprite(5, 9.0), sprite(10, 0.0), sprite(20, 1.0)],
+            vec![2],
+        );
+
+        let alignment = align_sprites(&old.sprites, &new.sprites);
+        assert_eq!(alignment.added, [0]);
+        assert!(alignment.removed.is_empty());
+        assert!(alignment.pairs.iter().all(|p| p.pairing == Pairing::Kept));
+        assert_eq!(
+            alignment.moves(),
+            [IndexMove { old: 0, new: 1 }, IndexMove { old: 1, new: 2 }]
+        );
+        assert!(diff_index_fields(&old, &new, &alignment).is_empty());
+
+        let edited = object(new.sprites.clone(), vec![1]);
+        let changes = diff_index_fields(&old, &edited, &alignment);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "headIndex");
+        assert_eq!(changes[0].remapped, [2]);
+    }
+
+    #[test]
+    fn test_reorder_and_replace() {
+        let old = [sprite(1, 0.0), sprite(2, 1.0), sprite(3, 2.0)];
+        let new = [sprite(2, 1.0), sprite(4, 2.0), sprite(1, 0.0)];
+
+        assert_eq!(
+            align_sprites(&old, &new).pairs,
+            [
+                SpritePair {
+                    old: 1,
+                    new: 0,
+                    pairing: Pairing::Kept
+                },
+                SpritePair {
+                    old: 2,
+                    new: 1,
+                    pairing: Pairing::Replaced
+                },
+                SpritePair {
+                    old: 0,
+                    new: 2,
+                    pairing: Pairing::Reordered
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::types::Object;
+
+/// One place a sprite is used: the object and the index into its sprites.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteUsage {
+    pub object_id: u64,
+    pub sprite_index: usize,
+}
+
+/// Reverse index from sprite id to every object sprite referencing it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SpriteIndex {
+    usages: BTreeMap<u64, Vec<SpriteUsage>>,
+}
+
+impl SpriteIndex {
+    pub fn build(objects: &[Object]) -> Self {
+        let mut usages: BTreeMap<u64, Vec<SpriteUsage>> = BTreeMap::new();
+        for object in objects {
+            for (sprite_index, sprite) in object.sprites.iter().enumerate() {
+                usages.entry(sprite.id).or_default().push(SpriteUsage {
+                    object_id: object.id,
+                    sprite_index,
+                });
+            }
+        }
+
+        for list in usages.values_mut() {
+            list.sort();
+        }
+        Self { usages }
+    }
+
+    /// Usages of the sprite, ordered by object id then sprite index.
+    pub fn usages(&self, sprite_id: u64) -> &[SpriteUsage] {
+        self.usages.get(&sprite_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Ids of the objects using the sprite, each listed once.
+    pub fn object_ids(&self, sprite_id: u64) -> Vec<u64> {
+        let mut ids: Vec<u64> =
+            self.usages(sprite_id).iter().map(|u| u.object_id).collect();
+        ids.dedup();
+
+        ids
+    }
+
+    /// Every referenced sprite id, in ascending order.
+    pub fn sprite_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.usages.keys().copied()
+    }
+
+    pub fn contains(&self, sprite_id: u64) -> bool {
+        self.usages.contains_key(&sprite_id)
+    }
+
+    /// Total number of sprite references.
+    pub fn reference_count(&self) -> usize {
+        self.usages.values().map(Vec::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod sprite_index_tests {
+    use crate::{
+        parser::types::{Object, Sprite},
+        sprite_index::{SpriteIndex, SpriteUsage},
+    };
+
+    #[test]
+    fn test() {
+        let object = |id: u64, sprite_ids: &[u64]| Object {
+            id,
+            sprites: sprite_ids
+                .iter()
+                .map(|id| Sprite {
+                    id: *id,
+                    ..Sprite::default()
+                })
+                .collect(),
+            ..Object::default()
+        };
+        let index = SpriteIndex::build(&[
+            object(9, &[1304, 5, 1304]),
+            object(3, &[1304]),
+        ]);
+
+        assert_eq!(
+            index.usages(1304),
+            [
+                SpriteUsage {
+                    object_id: 3,
+                    sprite_index: 0
+                },
+                SpriteUsage {
+                    object_id: 9,
+                    sprite_index: 0
+                },
+                SpriteUsage {
+                    object_id: 9,
+                    sprite_index: 2
+                },
+            ]
+        );
+        assert_eq!(index.object_ids(1304), vec![3, 9]);
+        assert_eq!(index.usages(77), []);
+        assert_eq!(index.sprite_ids().collect::<Vec<_>>(), vec![5, 1304]);
+        assert_eq!(index.reference_count(), 4);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::RgbaImage;
+
+use crate::geometry::SpriteSize;
+
+/// Decoded images kept around before the cache is dropped and refilled.
+const CACHE_LIMIT: usize = 4096;
+
+/// Lists the `<id>.tga` files in the sprites directory.
+pub fn sprite_files(
+    sprites_dir: &Path,
+) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(sprites_dir)? {
+        let path = entry?.path();
+        let id = match path.extension() {
+            Some(ext) if ext == "tga" => path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok()),
+            _ => None,
+        };
+
+        if let Some(id) = id {
+            files.push((id, path));
+        }
+    }
+    Ok(files)
+}
+
+pub fn load_sprite_image(path: &Path) -> anyhow::Result<RgbaImage> {
+    Ok(image::open(path)?.to_rgba8())
+}
+
+/// Lazily decodes `<id>.tga` files from a sprites directory.
+pub struct SpriteImages {
+    dir: PathBuf,
+    cache: HashMap<u64, Option<RgbaImage>>,
+}
+
+impl SpriteImages {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.tga", id))
+    }
+
+    /// Returns `None` for sprites whose file is missing or can't be decoded.
+    pub fn get(&mut self, id: u64) -> Option<&RgbaImage> {
+        if !self.cache.contains_key(&id) {
+            if self.cache.len() >= CACHE_LIMIT {
+                self.cache.clear();
+            }
+            let image = load_sprite_image(&self.path(id)).ok();
+            self.cache.insert(id, image);
+        }
+
+        self.cache.get(&id).and_then(Option::as_ref)
+    }
+
+    pub fn size(&mut self, id: u64) -> Option<SpriteSize> {
+        self.get(id).map(|image| SpriteSize {
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+}
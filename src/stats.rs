@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::types::{ColorRGB, Number},
+    sprites::{load_sprite_image, sprite_files},
+};
+
+/// Region of a sprite image left after cutting away fully transparent
+/// rows and columns, in image pixels from the top left corner.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimmedBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteStats {
+    pub width: u32,
+    pub height: u32,
+    pub trimmed: Option<TrimmedBox>,
+    /// Share of pixels that aren't fully transparent.
+    pub opaque_ratio: f64,
+    /// Alpha weighted mean color of the visible pixels.
+    pub average_color: Option<ColorRGB>,
+    /// Mean color of the most covered bucket of similar colors.
+    pub dominant_color: Option<ColorRGB>,
+    pub fully_transparent: bool,
+}
+
+/// Bits kept per channel when bucketing colors for the dominant color.
+const BUCKET_BITS: u8 = 4;
+
+pub fn sprite_stats(image: &RgbaImage) -> SpriteStats {
+    let mut trimmed: Option<(u32, u32, u32, u32)> = None;
+    let mut visible = 0u64;
+    let mut total = [0u64; 4];
+    // bucket -> summed alpha weighted r, g, b and alpha
+    let mut buckets: BTreeMap<u32, [u64; 4]> = BTreeMap::new();
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            continue;
+        }
+        visible += 1;
+        trimmed = Some(match trimmed {
+            None => (x, y, x, y),
+            Some((x0, y0, x1, y1)) => {
+                (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+            }
+        });
+
+        let shift = 8 - BUCKET_BITS;
+        let key = ((r >> shift) as u32) << (2 * BUCKET_BITS)
+            | ((g >> shift) as u32) << BUCKET_BITS
+            | (b >> shift) as u32;
+        let bucket = buckets.entry(key).or_default();
+        for sum in [&mut total, bucket] {
+            sum[0] += r as u64 * a as u64;
+            sum[1] += g as u64 * a as u64;
+            sum[2] += b as u64 * a as u64;
+            sum[3] += a as u64;
+        }
+    }
+
+    let pixels = image.width() as u64 * image.height() as u64;
+    let dominant = buckets.values().max_by_key(|sum| sum[3]).copied();
+
+    SpriteStats {
+        width: image.width(),
+        height: image.height(),
+        trimmed: trimmed.map(|(x0, y0, x1, y1)| TrimmedBox {
+            x: x0,
+            y: y0,
+            width: x1 - x0 + 1,
+            height: y1 - y0 + 1,
+        }),
+        opaque_ratio: if pixels == 0 {
+            0.0
+        } else {
+            visible as f64 / pixels as f64
+        },
+        average_color: mean_color(total),
+        dominant_color: dominant.and_then(mean_color),
+        fully_transparent: visible == 0,
+    }
+}
+
+fn mean_color(sum: [u64; 4]) -> Option<ColorRGB> {
+    if sum[3] == 0 {
+        return None;
+    }
+    let channel = |c: u64| Number(c as f64 / sum[3] as f64 / 255.0);
+
+    Some(ColorRGB {
+        r: channel(sum[0]),
+        g: channel(sum[1]),
+        b: channel(sum[2]),
+    })
+}
+
+/// Computes the stats of every `<id>.tga` file in the sprites directory,
+/// keyed by sprite id. Files that can't be decoded are skipped.
+pub fn collect_sprite_stats(
+    sprites_dir: &Path,
+) -> anyhow::Result<BTreeMap<u64, SpriteStats>> {
+    let mut stats = BTreeMap::new();
+    for (id, path) in sprite_files(sprites_dir)? {
+        if let Ok(image) = load_sprite_image(&path) {
+            stats.insert(id, sprite_stats(&image));
+        }
+    }
+    Ok(stats)
+}
+
+fn hex_color(color: &Option<ColorRGB>) -> String {
+    let channel = |n: &Number| (n.0 * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    match color {
+        Some(c) => format!(
+            "#{:02x}{:02x}{:02x}",
+            channel(&c.r),
+            channel(&c.g),
+            channel(&c.b)
+        ),
+        None => String::new(),
+    }
+}
+
+/// Formats the stats as CSV with one row per sprite and colors as hex.
+pub fn sprite_stats_csv(stats: &BTreeMap<u64, SpriteStats>) -> String {
+    let mut csv = String::from(
+        "id,width,height,trimX,trimY,trimWidth,trimHeight,opaqueRatio,\
+         averageColor,dominantColor,fullyTransparent\n",
+    );
+    for (id, s) in stats {
+        let trimmed = s.trimmed.unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{:.6},{},{},{}",
+            id,
+            s.width,
+            s.height,
+            trimmed.x,
+            trimmed.y,
+            trimmed.width,
+            trimmed.height,
+            s.opaque_ratio,
+            hex_color(&s.average_color),
+            hex_color(&s.dominant_color),
+            s.fully_transparent
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod sprite_stats_tests {
+    use image::{Rgba, RgbaImage};
+
+    use crate::{
+        parser::types::{ColorRGB, Number},
+        stats::{sprite_stats, TrimmedBox},
+    };
+
+    #[test]
+    fn test() {
+        let mut image = RgbaImage::new(4, 4);
+        image.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        image.put_pixel(2, 1, Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 2, Rgba([0, 0, 255, 255]));
+
+        let stats = sprite_stats(&image);
+        assert_eq!(
+            stats.trimmed,
+            Some(TrimmedBox {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 2
+            })
+        );
+        assert_eq!(stats.opaque_ratio, 3.0 / 16.0);
+        assert_eq!(
+            stats.dominant_color,
+            Some(ColorRGB {
+                r: Number(1.0),
+                g: Number(0.0),
+                b: Number(0.0)
+            })
+        );
+        assert!(!stats.fully_transparent);
+
+        let empty = sprite_stats(&RgbaImage::new(2, 2));
+        assert!(empty.fully_transparent);
+        assert_eq!(empty.trimmed, None);
+        assert_eq!(empty.average_color, None);
+    }
+}
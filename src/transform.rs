@@ -0,0 +1,326 @@
+use std::{f64::consts::TAU, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    geometry::{sprite_local_transform, Affine},
+    parser::types::{Object, Sprite},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformError {
+    /// `parent` is neither `-1` nor an index into the sprite list.
+    ParentOutOfRange { sprite: usize, parent: i64 },
+    /// Sprites whose parent chain loops back onto itself, in chain order.
+    Cycle { sprites: Vec<usize> },
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParentOutOfRange { sprite, parent } => write!(
+                f,
+                "sprite {} has out of range parent {}",
+                sprite, parent
+            ),
+            Self::Cycle { sprites } => {
+                let chain: Vec<String> =
+                    sprites.iter().map(|s| s.to_string()).collect();
+                write!(
+                    f,
+                    "sprite parents form a cycle: {}",
+                    chain.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+/// The parent hierarchy of an object's sprites.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SpriteTree {
+    pub parents: Vec<Option<usize>>,
+    pub children: Vec<Vec<usize>>,
+    pub roots: Vec<usize>,
+}
+
+impl SpriteTree {
+    pub fn build(sprites: &[Sprite]) -> Result<Self, TransformError> {
+        let mut parents = Vec::with_capacity(sprites.len());
+        for (i, sprite) in sprites.iter().enumerate() {
+            let parent = match sprite.parent {
+                -1 => None,
+                p => match usize::try_from(p) {
+                    Ok(p) if p < sprites.len() => Some(p),
+                    _ => {
+                        return Err(TransformError::ParentOutOfRange {
+                            sprite: i,
+                            parent: p,
+                        });
+                    }
+                },
+            };
+            parents.push(parent);
+        }
+
+        // 0 = unvisited, 1 = on the current chain, 2 = reaches a root.
+        let mut state = vec![0u8; sprites.len()];
+        for start in 0..sprites.len() {
+            let mut chain = vec![];
+            let mut current = Some(start);
+
+            while let Some(i) = current {
+                match state[i] {
+                    2 => break,
+                    1 => {
+                        let from = chain.iter().position(|c| *c == i);
+                        return Err(TransformError::Cycle {
+                            sprites: chain.split_off(from.unwrap_or(0)),
+                        });
+                    }
+                    _ => {
+                        state[i] = 1;
+                        chain.push(i);
+                        current = parents[i];
+                    }
+                }
+            }
+            for i in chain {
+                state[i] = 2;
+            }
+        }
+
+        let mut children = vec![vec![]; sprites.len()];
+        let mut roots = vec![];
+        for (i, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(p) => children[*p].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        Ok(Self {
+            parents,
+            children,
+            roots,
+        })
+    }
+
+    /// Sprite indices ordered so every parent comes before its children.
+    pub fn topological_order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.parents.len());
+        let mut stack: Vec<usize> =
+            self.roots.iter().rev().copied().collect();
+
+        while let Some(i) = stack.pop() {
+            order.push(i);
+            stack.extend(self.children[i].iter().rev());
+        }
+        order
+    }
+}
+
+/// Absolute placement of a sprite in object space.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldTransform {
+    pub matrix: Affine,
+    pub x: f64,
+    pub y: f64,
+    /// Clockwise rotation in full turns, within `[0, 1)`.
+    pub rot: f64,
+    pub h_flip: bool,
+}
+
+/// Resolves every sprite of the object into object space, indexed like
+/// `object.sprites`.
+///
+/// Like the game's `drawObject`, `pos` is already in object space, so each
+/// sprite sits at its own `pos` whatever its parent. Parents only carry
+/// their children along when animated, see
+/// [`resolve_animated_transforms`].
+pub fn resolve_transforms(
+    object: &Object,
+) -> Result<Vec<WorldTransform>, TransformError> {
+    resolve_animated_transforms(object, &[])
+}
+
+/// Like [`resolve_transforms`], with an animation delta per sprite: an
+/// object space transform moving the sprite away from its `pos`, which
+/// carries over to its children the way the game's `drawObjectAnim` moves
+/// them with their parent. Sprites past the end of `deltas` don't move by
+/// themselves.
+pub fn resolve_animated_transforms(
+    object: &Object,
+    deltas: &[Affine],
+) -> Result<Vec<WorldTransform>, TransformError> {
+    let sprites = &object.sprites;
+    let tree = SpriteTree::build(sprites)?;
+    // What each sprite's own and its ancestors' deltas add up to.
+    let mut carried = vec![Affine::IDENTITY; sprites.len()];
+    let mut resolved = vec![WorldTransform::default(); sprites.len()];
+
+    for i in tree.topological_order() {
+        let sprite = &sprites[i];
+        let delta = deltas.get(i).copied().unwrap_or_default();
+        carried[i] = match tree.parents[i] {
+            None => delta,
+            Some(p) => carried[p].then(&delta),
+        };
+
+        let moved = carried[i];
+        let matrix = moved.then(&sprite_local_transform(sprite));
+        let moved_flip = moved.a * moved.d - moved.b * moved.c < 0.0;
+        let moved_rot = moved.c.atan2(moved.d) / TAU;
+        // A flipped delta mirrors the direction the sprite turns in.
+        let rot = if moved_flip {
+            moved_rot - sprite.rot.0
+        } else {
+            moved_rot + sprite.rot.0
+        };
+
+        resolved[i] = WorldTransform {
+            matrix,
+            x: matrix.tx,
+            y: matrix.ty,
+            rot: rot.rem_euclid(1.0),
+            h_flip: moved_flip != (sprite.h_flip.0 != 0.0),
+        };
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod resolve_transforms_tests {
+    use std::{env, fs, process};
+
+    use image::{Rgba, RgbaImage};
+
+    use crate::{
+        geometry::{bounding_box, load_sprite_sizes, Affine},
+        parser::types::{Number, Object, Position, Sprite},
+        render::render_object,
+        sprites::SpriteImages,
+        transform::{
+            resolve_animated_transforms, resolve_transforms, TransformError,
+        },
+    };
+
+    fn sprite(x: f64, y: f64, rot: f64, h_flip: bool, parent: i64) -> Sprite {
+        Sprite {
+            position: Position {
+                x: Number(x),
+                y: Number(y),
+            },
+            rot: Number(rot),
+            h_flip: Number(if h_flip { 1.0 } else { 0.0 }),
+            parent,
+            ..Sprite::default()
+        }
+    }
+
+    fn object(sprites: Vec<Sprite>) -> Object {
+        Object {
+            sprites,
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn test_parented_sprite_keeps_its_pos() {
+        let transforms = resolve_transforms(&object(vec![
+            sprite(5.0, 0.0, 0.0, false, 1),
+            sprite(10.0, 0.0, 0.25, true, -1),
+        ]))
+        .unwrap();
+
+        let child = transforms[0];
+        assert!((child.x - 5.0).abs() < 1e-9);
+        assert!(child.y.abs() < 1e-9);
+        assert!(child.rot.abs() < 1e-9);
+        assert!(!child.h_flip);
+        assert!((transforms[1].rot - 0.25).abs() < 1e-9);
+        assert!(transforms[1].h_flip);
+    }
+
+    #[test]
+    fn test_animation_deltas() {
+        let object = object(vec![
+            sprite(5.0, 0.0, 0.0, false, 1),
+            sprite(10.0, 0.0, 0.1, false, -1),
+            sprite(0.0, 0.0, 0.0, false, -1),
+        ]);
+        // The parent is moved up and turned a quarter around the origin.
+        let delta = Affine::translate(0.0, 3.0).then(&Affine::rotate(0.25));
+        let transforms =
+            resolve_animated_transforms(&object, &[Affine::IDENTITY, delta])
+                .unwrap();
+
+        let child = transforms[0];
+        assert!(child.x.abs() < 1e-9);
+        assert!((child.y - -2.0).abs() < 1e-9);
+        assert!((child.rot - 0.25).abs() < 1e-9);
+        assert!((transforms[1].rot - 0.35).abs() < 1e-9);
+        // Not a child of the moved sprite.
+        assert_eq!(transforms[2], resolve_transforms(&object).unwrap()[2]);
+    }
+
+    #[test]
+    fn test_matches_bounding_box_and_render() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_resolve_transforms_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 255]))
+            .save(dir.join("0.tga"))
+            .unwrap();
+
+        let object = object(vec![
+            sprite(0.0, 0.0, 0.0, false, -1),
+            sprite(7.0, 3.0, 0.0, false, 0),
+        ]);
+        let child = resolve_transforms(&object).unwrap()[1];
+        assert!((child.x - 7.0).abs() < 1e-9);
+        assert!((child.y - 3.0).abs() < 1e-9);
+
+        let sizes = load_sprite_sizes(&dir).unwrap();
+        let bounds = bounding_box(&object, &sizes).unwrap();
+        assert!((bounds.max_x - (child.x + 1.0)).abs() < 1e-9);
+        assert!((bounds.max_y - (child.y + 1.0)).abs() < 1e-9);
+
+        let rendered = render_object(&object, &mut SpriteImages::new(&dir))
+            .unwrap()
+            .unwrap();
+        // The canvas runs from (-1, 4) at its top left, so the child
+        // covers columns 7 and 8 of rows 0 and 1.
+        assert_eq!((rendered.width(), rendered.height()), (9, 5));
+        assert_eq!(rendered.get_pixel(8, 0)[3], 255);
+        assert_eq!(rendered.get_pixel(8, 2)[3], 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_parents() {
+        assert_eq!(
+            resolve_transforms(&object(vec![sprite(
+                0.0, 0.0, 0.0, false, 3
+            )])),
+            Err(TransformError::ParentOutOfRange {
+                sprite: 0,
+                parent: 3
+            })
+        );
+        assert_eq!(
+            resolve_transforms(&object(vec![
+                sprite(0.0, 0.0, 0.0, false, -1),
+                sprite(0.0, 0.0, 0.0, false, 2),
+                sprite(0.0, 0.0, 0.0, false, 1),
+            ])),
+            Err(TransformError::Cycle {
+                sprites: vec![1, 2]
+            })
+        );
+    }
+}
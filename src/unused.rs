@@ -0,0 +1,170 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::{ObjectDocument, Value},
+    sprite_index::{SpriteIndex, SpriteUsage},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingSprite {
+    pub sprite_id: u64,
+    pub usages: Vec<SpriteUsage>,
+}
+
+/// An object file that doesn't parse, with the sprite ids its `spriteID`
+/// lines reference all the same.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnparsedFile {
+    pub path: PathBuf,
+    pub sprite_ids: Vec<u64>,
+}
+
+impl UnparsedFile {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let document = ObjectDocument::parse(&fs::read_to_string(path)?);
+        let sprite_ids = document
+            .fields()
+            .filter(|f| f.key == "spriteID")
+            .filter_map(|f| match f.value() {
+                Some(Value::Int(id)) => u64::try_from(*id).ok(),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            sprite_ids,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteReferenceReport {
+    /// Sprite files no object references.
+    pub unused: Vec<u64>,
+    /// Referenced sprites without a sprite file.
+    pub missing: Vec<MissingSprite>,
+    /// Object files that don't parse. Their sprites are never reported as
+    /// unused.
+    pub unparsed: Vec<UnparsedFile>,
+}
+
+/// Compares the sprites objects reference with the sprite files on disk.
+/// Sprites referenced by files that don't parse count as used, so nothing
+/// they need is listed for deletion.
+pub fn sprite_reference_report(
+    index: &SpriteIndex,
+    unparsed: Vec<UnparsedFile>,
+    sprite_files: &BTreeSet<u64>,
+) -> SpriteReferenceReport {
+    let referenced_by_unparsed: BTreeSet<u64> = unparsed
+        .iter()
+        .flat_map(|file| file.sprite_ids.iter().copied())
+        .collect();
+    let unused = sprite_files
+        .iter()
+        .copied()
+        .filter(|id| {
+            !index.contains(*id) && !referenced_by_unparsed.contains(id)
+        })
+        .collect();
+    let missing = index
+        .sprite_ids()
+        .filter(|id| !sprite_files.contains(id))
+        .map(|sprite_id| MissingSprite {
+            sprite_id,
+            usages: index.usages(sprite_id).to_vec(),
+        })
+        .collect();
+
+    SpriteReferenceReport {
+        unused,
+        missing,
+        unparsed,
+    }
+}
+
+#[cfg(test)]
+mod sprite_reference_report_tests {
+    use std::{collections::BTreeSet, env, fs, path::PathBuf, process};
+
+    use crate::{
+        parser::types::{Object, Sprite},
+        sprite_index::{SpriteIndex, SpriteUsage},
+        unused::{
+            sprite_reference_report, MissingSprite, SpriteReferenceReport,
+            UnparsedFile,
+        },
+    };
+
+    #[test]
+    fn test() {
+        let index = SpriteIndex::build(&[Object {
+            id: 4,
+            sprites: [1, 2]
+                .map(|id| Sprite {
+                    id,
+                    ..Sprite::default()
+                })
+                .to_vec(),
+            ..Object::default()
+        }]);
+
+        let unparsed = vec![UnparsedFile {
+            path: PathBuf::from("8.txt"),
+            sprite_ids: vec![5],
+        }];
+
+        assert_eq!(
+            sprite_reference_report(
+                &index,
+                unparsed.clone(),
+                &BTreeSet::from([1, 3, 5])
+            ),
+            SpriteReferenceReport {
+                unused: vec![3],
+                missing: vec![MissingSprite {
+                    sprite_id: 2,
+                    usages: vec![SpriteUsage {
+                        object_id: 4,
+                        sprite_index: 1
+                    }]
+                }],
+                unparsed,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unparsed_file() {
+        let dir = env::temp_dir()
+            .join(format!("tsmp_unparsed_file_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("8.txt");
+        fs::write(
+            &path,
+            "id=8\nBroken\nnumSprites=2\nspriteID=12\npos=oops\n\
+             spriteID=-3\nspriteID=40\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            UnparsedFile::read(&path).unwrap(),
+            UnparsedFile {
+                path: path.clone(),
+                sprite_ids: vec![12, 40],
+            }
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::types::{Object, ObjectKind},
+    sprite_diff::index_fields,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "problem",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Problem {
+    /// Points past the end of the sprite list, or is negative other than
+    /// `-1`.
+    OutOfRange {
+        index: i64,
+        num_sprites: usize,
+    },
+    Duplicate {
+        index: i64,
+    },
+    /// `-1` means the list is empty, so it can't go with real indices.
+    NoneMixedWithIndices,
+    /// Person objects need a head and a body sprite.
+    MissingPersonIndex,
+}
+
+/// A problem with one index list field of an object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub object_id: u64,
+    /// Its key in [`index_fields`].
+    pub field: String,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { index, num_sprites } => write!(
+                f,
+                "index {} is out of range for {} sprites",
+                index, num_sprites
+            ),
+            Self::Duplicate { index } => {
+                write!(f, "index {} is listed more than once", index)
+            }
+            Self::NoneMixedWithIndices => {
+                write!(f, "-1 is mixed with sprite indices")
+            }
+            Self::MissingPersonIndex => {
+                write!(f, "person object has no sprite here")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.object_id, self.field, self.problem)
+    }
+}
+
+/// Checks the index list fields of an object against its sprites.
+pub fn validate(object: &Object) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let num_sprites = object.sprites.len();
+    let is_person = matches!(object.kind, ObjectKind::Person(_));
+
+    for (field, indices) in index_fields(object) {
+        let mut problem = |problem| {
+            diagnostics.push(Diagnostic {
+                object_id: object.id,
+                field: field.to_string(),
+                problem,
+            })
+        };
+
+        let has_none = indices.contains(&-1);
+        let real: Vec<i64> =
+            indices.iter().copied().filter(|&i| i != -1).collect();
+
+        if has_none && !real.is_empty() {
+            problem(Problem::NoneMixedWithIndices);
+        }
+        for (n, &index) in real.iter().enumerate() {
+            if index < 0 || index as usize >= num_sprites {
+                problem(Problem::OutOfRange { index, num_sprites });
+            }
+            // Only the second time an index shows up.
+            if real[..n].iter().filter(|&&i| i == index).count() == 1 {
+                problem(Problem::Duplicate { index });
+            }
+        }
+        if is_person
+            && matches!(field, "headIndex" | "bodyIndex")
+            && real.is_empty()
+        {
+            problem(Problem::MissingPersonIndex);
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use crate::{
+        parser::types::{Object, ObjectKind, PersonCharacteristic, Sprite},
+        validate::{validate, Diagnostic, Problem},
+    };
+
+    #[test]
+    fn test_validate() {
+        let object = Object {
+            id: 19,
+            kind: ObjectKind::Person(PersonCharacteristic::Feminine),
+            sprites: vec![Sprite::default(); 3],
+            head_index: vec![-1],
+            body_index: vec![0, 2, 0, 0],
+            back_foot_index: vec![-1, 1],
+            front_foot_index: vec![3, -2],
+            ..Object::default()
+        };
+        let diagnostic = |field: &str, problem| Diagnostic {
+            object_id: 19,
+            field: field.to_string(),
+            problem,
+        };
+
+        assert_eq!(
+            validate(&object),
+            [
+                diagnostic("headIndex", Problem::MissingPersonIndex),
+                diagnostic("bodyIndex", Problem::Duplicate { index: 0 }),
+                diagnostic("backFootIndex", Problem::NoneMixedWithIndices),
+                diagnostic(
+                    "frontFootIndex",
+                    Problem::OutOfRange {
+                        index: 3,
+                        num_sprites: 3
+                    }
+                ),
+                diagnostic(
+                    "frontFootIndex",
+                    Problem::OutOfRange {
+                        index: -2,
+                        num_sprites: 3
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            validate(&object)[1].to_string(),
+            "19 bodyIndex: index 0 is listed more than once"
+        );
+    }
+
+    #[test]
+    fn test_repeated_duplicates() {
+        let object = Object {
+            id: 4,
+            sprites: vec![Sprite::default(); 2],
+            head_index: vec![1, 0, 1, 0, 1],
+            body_index: vec![-1],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            ..Object::default()
+        };
+        let problems: Vec<Problem> =
+            validate(&object).into_iter().map(|d| d.problem).collect();
+
+        // Reported once each, where the index shows up a second time.
+        assert_eq!(
+            problems,
+            [
+                Problem::Duplicate { index: 1 },
+                Problem::Duplicate { index: 0 }
+            ]
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt::Write;
+
+use crate::parser::types::{
+    ClothingObject, NonPersonObject, Number, Object, ObjectKind,
+    PersonCharacteristic, Position, Sprite,
+};
+
+/// Writes the object in the game's `key=value` object file layout, limited
+/// to the keys `parse_object` reads.
+///
+/// `numSprites` is taken from the length of `sprites`. Floats are written
+/// like the game's `%f`, and flag-like numbers as integers when they are
+/// whole.
+///
+/// Empty index lists are written as `-1`, the game's "no sprite", which
+/// `parse_object` reads back as `vec![-1]` rather than an empty list.
+pub fn to_object_text(object: &Object) -> String {
+    let mut text = String::new();
+    // Writing into a `String` can't fail.
+    let _ = write_object(&mut text, object);
+
+    text
+}
+
+fn write_object(out: &mut String, object: &Object) -> std::fmt::Result {
+    let (person, male) = match &object.kind {
+        ObjectKind::Person(PersonCharacteristic::Feminine) => (1, 0),
+        ObjectKind::Person(PersonCharacteristic::Masculine) => (1, 1),
+        ObjectKind::NonPerson(_) => (0, 0),
+    };
+    let (clothing, offset) = match &object.kind {
+        ObjectKind::NonPerson(NonPersonObject::Clothing(c)) => match c {
+            ClothingObject::Shoe(p) => ("s", p),
+            ClothingObject::Tunic(p) => ("t", p),
+            ClothingObject::Hat(p) => ("h", p),
+            ClothingObject::Bottom(p) => ("b", p),
+            ClothingObject::Backpack(p) => ("p", p),
+        },
+        _ => ("n", &Position::default()),
+    };
+
+    writeln!(out, "id={}", object.id)?;
+    writeln!(out, "{}", object.description)?;
+    writeln!(out, "person={}", person)?;
+    writeln!(out, "male={}", male)?;
+    writeln!(out, "clothing={}", clothing)?;
+    writeln!(
+        out,
+        "clothingOffset={},{}",
+        float(&offset.x),
+        float(&offset.y)
+    )?;
+    writeln!(out, "numSprites={}", object.sprites.len())?;
+
+    for sprite in &object.sprites {
+        write_sprite(out, sprite)?;
+    }
+
+    if let Some(behind) = &object.sprites_drawn_behind {
+        writeln!(out, "spritesDrawnBehind={}", index_list(behind))?;
+    }
+    if let Some(blend) = &object.sprites_additive_blend {
+        writeln!(out, "spritesAdditiveBlend={}", index_list(blend))?;
+    }
+    writeln!(out, "headIndex={}", index_list(&object.head_index))?;
+    writeln!(out, "bodyIndex={}", index_list(&object.body_index))?;
+    writeln!(out, "backFootIndex={}", index_list(&object.back_foot_index))?;
+    write!(
+        out,
+        "frontFootIndex={}",
+        index_list(&object.front_foot_index)
+    )?;
+
+    let has_uses = object.num_uses != 1
+        || object.use_chance.is_some()
+        || !object.use_vanish_index.is_empty()
+        || !object.use_appear_index.is_empty();
+    if has_uses {
+        write!(out, "\nnumUses={}", object.num_uses)?;
+        if let Some(chance) = &object.use_chance {
+            write!(out, ",{}", float(chance))?;
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "useVanishIndex={}",
+            index_list(&object.use_vanish_index)
+        )?;
+        write!(
+            out,
+            "useAppearIndex={}",
+            index_list(&object.use_appear_index)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_sprite(out: &mut String, sprite: &Sprite) -> std::fmt::Result {
+    writeln!(out, "spriteID={}", sprite.id)?;
+    writeln!(
+        out,
+        "pos={},{}",
+        float(&sprite.position.x),
+        float(&sprite.position.y)
+    )?;
+    writeln!(out, "rot={}", float(&sprite.rot))?;
+    writeln!(out, "hFlip={}", whole(&sprite.h_flip))?;
+    writeln!(
+        out,
+        "color={},{},{}",
+        float(&sprite.color.r),
+        float(&sprite.color.g),
+        float(&sprite.color.b)
+    )?;
+    writeln!(
+        out,
+        "ageRange={},{}",
+        float(&sprite.age_range.min),
+        float(&sprite.age_range.max)
+    )?;
+    writeln!(out, "parent={}", sprite.parent)?;
+    writeln!(
+        out,
+        "invisHolding={},invisWorn={},behindSlots={}",
+        whole(&sprite.invis_holding),
+        whole(&sprite.invis_worn),
+        whole(&sprite.behind_slots)
+    )?;
+    if let Some(invis_cont) = &sprite.invis_cont {
+        writeln!(out, "invisCont={}", whole(invis_cont))?;
+    }
+    if let Some(ignored_cont) = &sprite.ignored_cont {
+        writeln!(out, "ignoredCont={}", whole(ignored_cont))?;
+    }
+    Ok(())
+}
+
+/// Formats like C's `%f`.
+pub(crate) fn float(n: &Number) -> String {
+    format!("{:.6}", n.0)
+}
+
+/// Formats whole numbers like `%d`, falling back to `%f` so nothing is
+/// lost.
+fn whole(n: &Number) -> String {
+    if n.0.fract() == 0.0 {
+        format!("{}", n.0 as i64)
+    } else {
+        float(n)
+    }
+}
+
+/// Comma separated indices, `-1` when empty.
+pub(crate) fn index_list(list: &[i64]) -> String {
+    if list.is_empty() {
+        return "-1".to_string();
+    }
+    let items: Vec<String> = list.iter().map(|i| i.to_string()).collect();
+
+    items.join(",")
+}
+
+#[cfg(test)]
+mod to_object_text_tests {
+    use winnow::Parser;
+
+    use crate::{
+        parser::{
+            parse_object,
+            types::{
+                ClothingObject, ColorRGB, NonPersonObject, Number, Object,
+                ObjectKind, PersonCharacteristic, Position, Sprite,
+            },
+        },
+        writer::to_object_text,
+    };
+
+    fn round_trip(object: &Object) {
+        let text = to_object_text(object);
+        let parsed = parse_object.parse(text.as_str()).unwrap();
+
+        assert_eq!(&parsed, object);
+        assert_eq!(to_object_text(&parsed), text);
+    }
+
+    fn sprite(id: u64, x: f64, rot: f64) -> Sprite {
+        Sprite {
+            id,
+            position: Position {
+                x: Number(x),
+                y: Number(-34.0),
+            },
+            rot: Number(rot),
+            color: ColorRGB {
+                r: Number(1.0),
+                g: Number(0.5),
+                b: Number(0.25),
+            },
+            parent: -1,
+            ..Sprite::default()
+        }
+    }
+
+    #[test]
+    fn test_written_layout() {
+        let object = Object {
+            id: 12,
+            description: "Hat".to_string(),
+            kind: ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Hat(Position {
+                    x: Number(0.0),
+                    y: Number(-2.5),
+                }),
+            )),
+            num_sprites: 1,
+            sprites: vec![Sprite {
+                invis_cont: Some(Number(0.0)),
+                ..sprite(553, 3.0, -0.025)
+            }],
+            head_index: vec![-1],
+            body_index: vec![-1],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            num_uses: 1,
+            ..Object::default()
+        };
+
+        assert_eq!(
+            to_object_text(&object),
+            "id=12
+Hat
+person=0
+male=0
+clothing=h
+clothingOffset=0.000000,-2.500000
+numSprites=1
+spriteID=553
+pos=3.000000,-34.000000
+rot=-0.025000
+hFlip=0
+color=1.000000,0.500000,0.250000
+ageRange=0.000000,0.000000
+parent=-1
+invisHolding=0,invisWorn=0,behindSlots=0
+invisCont=0
+headIndex=-1
+bodyIndex=-1
+backFootIndex=-1
+frontFootIndex=-1"
+        );
+        round_trip(&object);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&Object {
+            id: 19,
+            description: "Elder Woman #dummy".to_string(),
+            kind: ObjectKind::Person(PersonCharacteristic::Masculine),
+            num_sprites: 3,
+            sprites: vec![
+                sprite(1, 0.5, 0.0),
+                Sprite {
+                    ignored_cont: Some(Number(1.0)),
+                    ..sprite(2, -8.0, 0.125)
+                },
+                sprite(3, 12.0, 0.0),
+            ],
+            sprites_drawn_behind: Some(vec![0, 2]),
+            sprites_additive_blend: Some(vec![1]),
+            head_index: vec![1],
+            body_index: vec![0],
+            back_foot_index: vec![2],
+            front_foot_index: vec![0, 2],
+            num_uses: 4,
+            use_chance: Some(Number(0.5)),
+            use_vanish_index: vec![0, 1],
+            use_appear_index: vec![-1],
+            bounding_box: None,
+        });
+        round_trip(&Object {
+            kind: ObjectKind::NonPerson(NonPersonObject::Clothing(
+                ClothingObject::Backpack(Position::default()),
+            )),
+            sprites: vec![sprite(4, 1.0, 0.0)],
+            num_sprites: 1,
+            sprites_drawn_behind: Some(vec![0]),
+            head_index: vec![-1],
+            body_index: vec![-1],
+            back_foot_index: vec![-1],
+            front_foot_index: vec![-1],
+            num_uses: 1,
+            ..Object::default()
+        });
+    }
+
+    #[test]
+    fn test_round_trip_game_text() {
+        let source = "id=30
+Bowl of Gooseberries
+containable=1
+person=0,noSpawn=0
+male=0
+deathMarker=0
+clothing=n
+clothingOffset=0.000000,0.000000
+numSprites=2
+spriteID=337
+pos=0.000000,-4.000000
+rot=0.000000
+hFlip=0
+color=1.000000,1.000000,1.000000
+ageRange=-1.000000,-1.000000
+parent=-1
+invisHolding=0,invisWorn=0,behindSlots=0
+spriteID=338
+pos=-1.000000,3.000000
+rot=0.975000
+hFlip=1
+color=0.690196,0.094118,0.164706
+ageRange=-1.000000,-1.000000
+parent=0
+invisHolding=0,invisWorn=0,behindSlots=0
+headIndex=-1
+bodyIndex=-1
+backFootIndex=-1
+frontFootIndex=-1
+numUses=4,1.000000
+useVanishIndex=1
+useAppearIndex=-1
+pixHeight=0";
+        let object = parse_object.parse(source).unwrap();
+
+        round_trip(&object);
+    }
+
+    #[test]
+    fn test_empty_index_list() {
+        let object = Object {
+            id: 1,
+            head_index: vec![],
+            ..Object::default()
+        };
+        let text = to_object_text(&object);
+        let parsed = parse_object.parse(text.as_str()).unwrap();
+
+        assert!(text.contains("headIndex=-1\n"));
+        assert_eq!(parsed.head_index, vec![-1]);
+        assert_eq!(to_object_text(&parsed), text);
+    }
+}